@@ -0,0 +1,1071 @@
+use crate::{
+    cache::Freshness,
+    cli::Args,
+    clock::{format_utc, parse_utc, SimClock},
+    eclipse::{illumination, EclipseEvent, Illumination},
+    frames::{ecef_to_geodetic, teme_to_ecef_state, teme_to_geodetic, EarthOrientation, GroundPos},
+    observer::{LookAngles, Observer},
+    passes::{sort_passes, Pass, PassSort},
+    predictions::{Predictions, Predictor},
+    projection::Projection,
+    propagation::{PropagationError, Propagator, MAX_EPOCH_AGE_DAYS},
+    refresh::RefreshEvent,
+    satellite::Satellite,
+    selection::{search_match, Selection},
+    source::skipped_note,
+    sun::{subsolar_point, sun_position},
+    viewport::Viewport,
+};
+use crossterm::event::{KeyCode, MouseEvent, MouseEventKind};
+use hifitime::prelude::*;
+use ratatui::layout::{Margin, Rect};
+use sgp4::Elements;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+/// finest ground track sample spacing, however far the map is zoomed in
+const TRACK_MIN_STEP_SECONDS: f64 = 5.0;
+/// most samples in one track, so long horizons with many satellites stay quick
+const TRACK_MAX_SAMPLES: f64 = 2000.0;
+/// how much one key press lengthens or shortens the ground tracks, in orbits
+const TRACK_ORBITS_STEP: f64 = 0.5;
+const MAX_TRACK_ORBITS: f64 = 16.0;
+/// range of ground track samples per orbit at zoom 1
+const TRACK_POINTS_RANGE: (f64, f64) = (8.0, 1440.0);
+/// how much one key press or wheel notch zooms the map
+const ZOOM_STEP: f64 = 1.5;
+/// how far one key press pans the map, as a fraction of the view
+const PAN_STEP: f64 = 0.2;
+/// how far one key press turns the globe at zoom 1, in degrees
+const TURN_STEP: f64 = 15.0;
+
+/// Which main view is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Map,
+    SkyPlot,
+}
+
+/// A line of text being typed into the status bar
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Prompt {
+    /// a UTC date and time for the clock to jump to
+    JumpTo(String),
+    /// edits `App::search` in place, narrowing the satellites shown as it changes
+    Search,
+}
+
+/// Everything the UI needs between frames
+pub struct App {
+    pub args: Args,
+    /// shared with the predictor's worker thread
+    pub satellites: Arc<Vec<Satellite>>,
+    pub freshness: Freshness,
+    /// when the elements on screen were fetched
+    pub last_loaded: Option<Epoch>,
+    /// one line about the last element load, shown under the map
+    pub status: String,
+    pub observer: Option<Observer>,
+    /// upcoming passes over the observer, kept sorted by `pass_sort`
+    pub passes: Vec<Pass>,
+    /// upcoming shadow entries and exits of every satellite, in time order
+    pub eclipses: Vec<EclipseEvent>,
+    /// computes `passes` and `eclipses` off the UI thread
+    predictor: Predictor,
+    /// window `passes` and `eclipses` cover
+    predicted: Option<(Epoch, Epoch)>,
+    /// window asked of the predictor and not yet received
+    requested: Option<(Epoch, Epoch)>,
+    /// counts element loads, so that predictions for replaced elements are dropped
+    generation: u64,
+    pub pass_sort: PassSort,
+    pub pass_reverse: bool,
+    pub show_passes: bool,
+    /// the table of tracked satellites under the map
+    pub show_list: bool,
+    /// only list passes where the satellite can be seen by eye or telescope
+    pub visible_only: bool,
+    pub view: View,
+    /// day/night shading on the map
+    pub show_night: bool,
+    /// ground track flown over the last horizon, as well as the upcoming one
+    pub show_past_track: bool,
+    /// coverage circles around every satellite
+    pub show_footprints: bool,
+    /// how many orbits of ground track are drawn ahead (and behind)
+    pub track_orbits: f64,
+    /// ground track samples per orbit at zoom 1
+    pub track_points: f64,
+    /// ground track samples by NORAD ID, kept between frames by `update_tracks`
+    tracks: HashMap<u64, TrackSamples>,
+    /// NORAD ID of the satellite picked with the arrow keys or `[` / `]`
+    pub selected: Option<u64>,
+    pub clock: SimClock,
+    pub projection: Projection,
+    pub viewport: Viewport,
+    /// keep the selected satellite in the middle of the map
+    pub follow: bool,
+    /// where the map was last drawn, for mouse zoom and drag
+    pub map_area: Rect,
+    /// cell the mouse was last dragged from
+    drag_from: Option<(u16, u16)>,
+    pub prompt: Option<Prompt>,
+    /// only satellites matching this are listed and drawn, see [`search_match`]
+    pub search: String,
+    pub quit: bool,
+}
+
+/// One sample of a ground track
+#[derive(Clone, Copy, Debug)]
+pub struct TrackPoint {
+    pub time: Epoch,
+    pub pos: GroundPos,
+    pub light: Illumination,
+}
+
+/// Ground track samples of one satellite at whole multiples of `step` seconds (TAI), so
+/// that as the clock moves only the samples newly in range are propagated
+struct TrackSamples {
+    step: i64,
+    /// grid index of `points[0]`
+    first: i64,
+    /// `None` where the satellite couldn't be propagated
+    points: VecDeque<Option<TrackPoint>>,
+}
+
+impl TrackSamples {
+    fn new(step: i64) -> Self {
+        TrackSamples {
+            step,
+            first: 0,
+            points: VecDeque::new(),
+        }
+    }
+
+    /// grid index of the last sample at or before `time`
+    fn index(&self, time: Epoch) -> i64 {
+        (time.to_tai_seconds() / self.step as f64).floor() as i64
+    }
+
+    fn time(&self, index: i64) -> Epoch {
+        Epoch::from_tai_seconds((index * self.step) as f64)
+    }
+
+    fn get(&self, index: i64) -> Option<TrackPoint> {
+        let offset = usize::try_from(index - self.first).ok()?;
+        self.points.get(offset).copied().flatten()
+    }
+
+    /// trims and extends the samples to cover grid indices `low` to `high`
+    fn cover(&mut self, low: i64, high: i64, mut sample: impl FnMut(Epoch) -> Option<TrackPoint>) {
+        let last = self.first + self.points.len() as i64 - 1;
+        if self.points.is_empty() || high < self.first || low > last {
+            self.points.clear();
+            self.first = low;
+        }
+        while self.first < low {
+            self.points.pop_front();
+            self.first += 1;
+        }
+        while self.first + self.points.len() as i64 - 1 > high {
+            self.points.pop_back();
+        }
+        while self.first > low {
+            self.first -= 1;
+            self.points.push_front(sample(self.time(self.first)));
+        }
+        while self.first + (self.points.len() as i64) <= high {
+            let next = self.first + self.points.len() as i64;
+            self.points.push_back(sample(self.time(next)));
+        }
+    }
+}
+
+/// A satellite that propagated fine at the current time
+pub struct Tracked<'a> {
+    pub sat: &'a Satellite,
+    pub position: GroundPos,
+    pub light: Illumination,
+    /// inertial speed in km/s
+    pub speed: f64,
+    /// upcoming ground track, starting at the current position
+    pub track: Vec<TrackPoint>,
+    /// ground track already flown, ending at the current position; empty unless enabled
+    pub past: Vec<TrackPoint>,
+    pub look: Option<LookAngles>,
+}
+
+/// Positions of every satellite matching the search at one instant
+pub struct Snapshot<'a> {
+    pub time: Epoch,
+    pub subsolar: GroundPos,
+    pub tracked: Vec<Tracked<'a>>,
+    pub lost: Vec<(&'a Satellite, PropagationError)>,
+    /// NORAD IDs of every satellite matching the search, tracked or lost
+    pub shown: HashSet<u64>,
+}
+
+impl Snapshot<'_> {
+    /// why satellites dropped out for being too far from their element epochs, e.g. after
+    /// jumping the clock, `None` if none did
+    pub fn epoch_notice(&self) -> Option<String> {
+        let (mut old, mut new) = (0, 0);
+        for (_, error) in &self.lost {
+            match error {
+                PropagationError::EpochTooFar { days } if *days > 0.0 => old += 1,
+                PropagationError::EpochTooFar { .. } => new += 1,
+                _ => {}
+            }
+        }
+        let parts: Vec<String> = [(old, "old"), (new, "new")]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, age)| format!("{} element sets too {}", count, age))
+            .collect();
+        (!parts.is_empty()).then(|| {
+            format!(
+                "{} for {} (over {} days from their epoch)",
+                parts.join(", "),
+                format_utc(self.time),
+                MAX_EPOCH_AGE_DAYS
+            )
+        })
+    }
+
+    /// whether a satellite is in this snapshot, i.e. matches the search
+    pub fn shows(&self, norad_id: u64) -> bool {
+        self.shown.contains(&norad_id)
+    }
+}
+
+impl App {
+    pub fn new(args: Args) -> Self {
+        let observer = args.observer.map(Observer::new);
+        let visible_only = args.visible_only;
+        let projection = args.projection;
+        let (track_orbits, track_points) = (args.track_orbits, args.track_points);
+        let predictor = Predictor::spawn(
+            observer,
+            args.earth_orientation,
+            args.min_elevation,
+            args.standard_magnitudes.clone(),
+        );
+        App {
+            args,
+            satellites: Arc::default(),
+            freshness: Freshness::Live,
+            last_loaded: None,
+            status: String::new(),
+            observer,
+            passes: Vec::new(),
+            eclipses: Vec::new(),
+            predictor,
+            predicted: None,
+            requested: None,
+            generation: 0,
+            pass_sort: PassSort::Aos,
+            pass_reverse: false,
+            show_passes: true,
+            show_list: true,
+            visible_only,
+            view: View::Map,
+            show_night: true,
+            show_footprints: true,
+            show_past_track: false,
+            track_orbits,
+            track_points,
+            tracks: HashMap::new(),
+            selected: None,
+            clock: SimClock::live(),
+            projection,
+            viewport: Viewport::new(projection.extent()),
+            follow: false,
+            map_area: Rect::default(),
+            drag_from: None,
+            prompt: None,
+            search: String::new(),
+            quit: false,
+        }
+    }
+
+    /// replaces the tracked satellites with the selected ones out of a fresh element load
+    pub fn load(&mut self, elements_vec: Vec<Elements>, at: Epoch) {
+        let loaded = elements_vec.len();
+        self.satellites = Arc::new(track(elements_vec, &self.args.selection));
+        if self.satellites.is_empty() {
+            self.status = format!("none of the {} element sets match the selection", loaded);
+        }
+        self.last_loaded = Some(at);
+        self.tracks.clear();
+        self.generation += 1;
+        self.predicted = None;
+        self.requested = None;
+    }
+
+    /// Keeps `passes` and `eclipses` covering `--days` either side of `time`, asking the
+    /// predictor for a window re-centred on `time` once it has drifted half of that away
+    /// from the middle, whichever way the clock runs. Finished windows are picked up here.
+    pub fn update_predictions(&mut self, time: Epoch) {
+        while let Some(predictions) = self.predictor.poll() {
+            self.receive(predictions);
+        }
+
+        let reach = Unit::Day * self.args.pass_days;
+        let centred = self
+            .requested
+            .or(self.predicted)
+            .is_some_and(|(start, end)| {
+                let middle = start + (end - start) / 2;
+                (time - middle).abs() <= reach / 2
+            });
+        if !centred {
+            let window = (time - reach, time + reach);
+            self.predictor
+                .request(self.generation, self.satellites.clone(), window);
+            self.requested = Some(window);
+        }
+    }
+
+    /// takes in a finished prediction window, unless it was for elements since replaced
+    fn receive(&mut self, predictions: Predictions) {
+        if predictions.generation != self.generation {
+            return;
+        }
+        self.passes = predictions.passes;
+        sort_passes(&mut self.passes, self.pass_sort, self.pass_reverse);
+        self.eclipses = predictions.eclipses;
+        self.predicted = Some(predictions.window);
+        if self.requested == Some(predictions.window) {
+            self.requested = None;
+        }
+    }
+
+    /// whether a prediction window is still being computed
+    pub fn predicting(&self) -> bool {
+        self.requested.is_some()
+    }
+
+    /// passes of `shown` satellites still to come or in progress at `time`, honouring the
+    /// visible-only filter
+    pub fn upcoming_passes<'a>(
+        &'a self,
+        time: Epoch,
+        shown: &'a HashSet<u64>,
+    ) -> impl Iterator<Item = &'a Pass> {
+        self.passes
+            .iter()
+            .filter(move |pass| pass.los >= time)
+            .filter(|pass| !self.visible_only || pass.visible)
+            .filter(|pass| shown.contains(&pass.norad_id))
+    }
+
+    /// moves the selection `delta` satellites forward or back, wrapping around
+    pub fn select_next(&mut self, delta: isize) {
+        let shown: Vec<u64> = self
+            .shown_satellites()
+            .map(|sat| sat.elements.norad_id)
+            .collect();
+        if shown.is_empty() {
+            self.selected = None;
+            return;
+        }
+        let count = shown.len() as isize;
+        let current = self
+            .selected
+            .and_then(|selected| shown.iter().position(|&id| id == selected));
+        let index = match current {
+            Some(index) => (index as isize + delta).rem_euclid(count),
+            None if delta < 0 => count - 1,
+            None => 0,
+        };
+        self.selected = Some(shown[index as usize]);
+    }
+
+    /// the satellites matching the search, in load order
+    pub fn shown_satellites(&self) -> impl Iterator<Item = &Satellite> {
+        self.satellites
+            .iter()
+            .filter(|sat| search_match(&self.search, &sat.elements))
+    }
+
+    /// NORAD IDs of the satellites matching the search, to check many passes or events
+    /// against without searching each time
+    pub fn shown_ids(&self) -> HashSet<u64> {
+        self.shown_satellites()
+            .map(|sat| sat.elements.norad_id)
+            .collect()
+    }
+
+    fn is_shown(&self, norad_id: u64) -> bool {
+        self.shown_satellites()
+            .any(|sat| sat.elements.norad_id == norad_id)
+    }
+
+    /// moves the selection to the first match if the search hid the selected satellite
+    fn keep_selection_shown(&mut self) {
+        if self
+            .selected
+            .is_some_and(|selected| !self.is_shown(selected))
+        {
+            let first = self
+                .shown_satellites()
+                .next()
+                .map(|sat| sat.elements.norad_id);
+            self.selected = first;
+        }
+    }
+
+    /// the pass shown in the sky plot: the current or next pass of the selected satellite,
+    /// or of any satellite if none is selected
+    pub fn sky_plot_pass(&self, time: Epoch) -> Option<&Pass> {
+        self.passes
+            .iter()
+            .filter(|pass| pass.los >= time)
+            .filter(|pass| {
+                self.selected
+                    .is_none_or(|selected| pass.norad_id == selected)
+            })
+            .min_by_key(|pass| pass.aos)
+    }
+
+    pub fn on_key(&mut self, code: KeyCode) {
+        match &mut self.prompt {
+            Some(Prompt::JumpTo(text)) => {
+                match code {
+                    KeyCode::Enter => {
+                        match parse_utc(text) {
+                            Ok(time) => self.clock.jump(time),
+                            Err(error) => self.status = error.to_string(),
+                        }
+                        self.prompt = None;
+                    }
+                    KeyCode::Esc => self.prompt = None,
+                    KeyCode::Backspace => {
+                        text.pop();
+                    }
+                    KeyCode::Char(c) => text.push(c),
+                    _ => {}
+                }
+                return;
+            }
+            Some(Prompt::Search) => {
+                match code {
+                    KeyCode::Enter => self.prompt = None,
+                    KeyCode::Esc => {
+                        self.search.clear();
+                        self.prompt = None;
+                    }
+                    KeyCode::Backspace => {
+                        self.search.pop();
+                    }
+                    KeyCode::Char(c) => self.search.push(c),
+                    KeyCode::Down => self.select_next(1),
+                    KeyCode::Up => self.select_next(-1),
+                    _ => {}
+                }
+                self.keep_selection_shown();
+                return;
+            }
+            None => {}
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => self.quit = true,
+            KeyCode::Char('v') => {
+                self.view = match self.view {
+                    View::Map => View::SkyPlot,
+                    View::SkyPlot => View::Map,
+                }
+            }
+            KeyCode::Char('n') => self.show_night = !self.show_night,
+            KeyCode::Char('c') => self.show_footprints = !self.show_footprints,
+            KeyCode::Char('b') => self.show_past_track = !self.show_past_track,
+            KeyCode::Char('{') => self.change_track_orbits(-TRACK_ORBITS_STEP),
+            KeyCode::Char('}') => self.change_track_orbits(TRACK_ORBITS_STEP),
+            KeyCode::Char('(') => self.change_track_points(0.5),
+            KeyCode::Char(')') => self.change_track_points(2.0),
+            KeyCode::Char(']') | KeyCode::Down => self.select_next(1),
+            KeyCode::Char('[') | KeyCode::Up => self.select_next(-1),
+            KeyCode::Esc => {
+                self.selected = None;
+                self.follow = false;
+            }
+            KeyCode::Char('t') => self.show_list = !self.show_list,
+            KeyCode::Char('p') => self.show_passes = !self.show_passes,
+            KeyCode::Char('o') => self.visible_only = !self.visible_only,
+            KeyCode::Char(' ') => self.clock.toggle_pause(),
+            KeyCode::Char('+') => self.clock.change_speed(1),
+            KeyCode::Char('-') => self.clock.change_speed(-1),
+            KeyCode::Char('.') => self.clock.step(Unit::Minute * 1),
+            KeyCode::Char(',') => self.clock.step(Unit::Minute * -1),
+            KeyCode::Char('>') => self.clock.step(Unit::Hour * 1),
+            KeyCode::Char('<') => self.clock.step(Unit::Hour * -1),
+            KeyCode::Char('g') => self.prompt = Some(Prompt::JumpTo(String::new())),
+            KeyCode::Char('/') => {
+                self.search.clear();
+                self.prompt = Some(Prompt::Search);
+            }
+            KeyCode::Char('l') => self.clock = SimClock::live(),
+            KeyCode::Char('z') => self.zoom(ZOOM_STEP),
+            KeyCode::Char('Z') => self.zoom(1.0 / ZOOM_STEP),
+            KeyCode::Char('H') => self.pan(-PAN_STEP, 0.0),
+            KeyCode::Char('L') => self.pan(PAN_STEP, 0.0),
+            KeyCode::Char('K') => self.pan(0.0, PAN_STEP),
+            KeyCode::Char('J') => self.pan(0.0, -PAN_STEP),
+            KeyCode::Char('0') => {
+                self.viewport = Viewport::new(self.map_extent());
+                self.follow = false;
+            }
+            KeyCode::Char('m') => {
+                self.projection = self.projection.next(self.args.globe_center);
+                self.viewport = Viewport::new(self.map_extent());
+            }
+            KeyCode::Char('f') => self.follow = !self.follow && self.selected.is_some(),
+            KeyCode::Char('s') => {
+                self.pass_sort = self.pass_sort.next();
+                sort_passes(&mut self.passes, self.pass_sort, self.pass_reverse);
+            }
+            KeyCode::Char('S') => {
+                self.pass_reverse = !self.pass_reverse;
+                sort_passes(&mut self.passes, self.pass_sort, self.pass_reverse);
+            }
+            _ => {}
+        }
+    }
+
+    /// zooms about the middle of the view, or the followed satellite
+    fn zoom(&mut self, factor: f64) {
+        let center = self.viewport.center;
+        self.viewport.zoom_at(factor, center);
+    }
+
+    /// moves the view, or turns the globe under it
+    fn pan(&mut self, dx: f64, dy: f64) {
+        self.follow = false;
+        match &mut self.projection {
+            Projection::Orthographic { lat, lon } => {
+                let step = TURN_STEP / PAN_STEP / self.viewport.zoom;
+                *lat = (*lat + dy * step).clamp(-90.0, 90.0);
+                *lon = (*lon + dx * step + 180.0).rem_euclid(360.0) - 180.0;
+            }
+            _ => self.viewport.pan(dx, dy),
+        }
+    }
+
+    /// the projection's extent, widened where needed to keep round maps round in the
+    /// area the map was last drawn in
+    fn map_extent(&self) -> ([f64; 2], [f64; 2]) {
+        let (x, y) = self.projection.extent();
+        if !self.projection.is_round() {
+            return (x, y);
+        }
+        // braille cells are twice as tall as they are wide
+        let inner = self.map_area.inner(&Margin::new(1, 1));
+        let aspect = inner.width.max(1) as f64 / (inner.height.max(1) * 2) as f64;
+        if aspect >= 1.0 {
+            ([x[0] * aspect, x[1] * aspect], y)
+        } else {
+            (x, [y[0] / aspect, y[1] / aspect])
+        }
+    }
+
+    /// wheel zooms about the pointer, dragging pans the map
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        let cell = (event.column, event.row);
+        let at = self.viewport.at_cell(self.map_area, cell.0, cell.1);
+        match event.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown if self.view == View::Map => {
+                let factor = if event.kind == MouseEventKind::ScrollUp {
+                    ZOOM_STEP
+                } else {
+                    1.0 / ZOOM_STEP
+                };
+                // following keeps the satellite centered, so zoom about it instead
+                match at.filter(|_| !self.follow) {
+                    Some(point) => self.viewport.zoom_at(factor, point),
+                    None => self.zoom(factor),
+                }
+            }
+            MouseEventKind::Down(_) => self.drag_from = at.map(|_| cell),
+            MouseEventKind::Drag(_) => {
+                if let Some(from) = self.drag_from {
+                    let inner = self.map_area.inner(&Margin::new(1, 1));
+                    let dx = (from.0 as f64 - cell.0 as f64) / inner.width.max(1) as f64;
+                    let dy = (cell.1 as f64 - from.1 as f64) / inner.height.max(1) as f64;
+                    self.follow = false;
+                    self.viewport.pan(dx, dy);
+                    self.drag_from = Some(cell);
+                }
+            }
+            MouseEventKind::Up(_) => self.drag_from = None,
+            _ => {}
+        }
+    }
+
+    /// keeps the map fitted to its area and centered on the selected satellite when
+    /// following it
+    pub fn update_view(&mut self, time: Epoch) {
+        self.viewport.fit(self.map_extent());
+        if !self.follow {
+            return;
+        }
+        let position = self
+            .satellites
+            .iter()
+            .find(|sat| Some(sat.elements.norad_id) == self.selected)
+            .and_then(|sat| sat.propagator.as_ref().ok())
+            .and_then(|propagator| propagator.propagate(time).ok())
+            .map(|prediction| {
+                teme_to_geodetic(prediction.position, time, &self.args.earth_orientation)
+            });
+        let Some(position) = position else {
+            return;
+        };
+        if let Projection::Orthographic { lat, lon } = &mut self.projection {
+            (*lat, *lon) = (position.lat, position.lon);
+            self.viewport.center_on((0.0, 0.0));
+        } else if let Some(point) = self.projection.project(position.lat, position.lon) {
+            self.viewport.center_on(point);
+        }
+    }
+
+    pub fn apply(&mut self, event: RefreshEvent, interval: Option<std::time::Duration>) {
+        match event {
+            RefreshEvent::Loaded {
+                elements,
+                skipped,
+                at,
+            } => {
+                self.status = format!(
+                    "refreshed {} element sets at {}{}",
+                    elements.len(),
+                    at,
+                    skipped_note(&skipped)
+                );
+                if let Some(interval) = interval {
+                    self.status.push_str(&format!(
+                        ", next refresh in {}",
+                        Duration::from_seconds(interval.as_secs_f64())
+                    ));
+                }
+                self.load(elements, at);
+                self.freshness = Freshness::Live;
+            }
+            RefreshEvent::Failed { error, at } => {
+                self.status = format!("refresh failed at {}: {}", at, error);
+                self.freshness = match self.last_loaded {
+                    Some(fetched_at) => Freshness::Cached {
+                        fetched_at,
+                        refresh_failed: true,
+                    },
+                    None => Freshness::Failed(error),
+                };
+            }
+        }
+    }
+
+    /// lengthens or shortens the ground tracks by `by` orbits, within
+    /// [`TRACK_ORBITS_STEP`] and [`MAX_TRACK_ORBITS`]
+    fn change_track_orbits(&mut self, by: f64) {
+        self.track_orbits = (self.track_orbits + by).clamp(TRACK_ORBITS_STEP, MAX_TRACK_ORBITS);
+    }
+
+    /// scales the ground track samples per orbit by `factor`, within [`TRACK_POINTS_RANGE`]
+    fn change_track_points(&mut self, factor: f64) {
+        let (min, max) = TRACK_POINTS_RANGE;
+        self.track_points = (self.track_points * factor).clamp(min, max);
+    }
+
+    /// sample spacing and count for a ground track of a satellite with orbital `period`,
+    /// finer when zoomed in so the lines stay smooth on screen
+    fn track_sampling(&self, period: Duration) -> (Duration, usize) {
+        let horizon = period.to_seconds() * self.track_orbits;
+        // whole seconds, so satellites with similar periods share sample times
+        let step = (period.to_seconds() / self.track_points / self.viewport.zoom)
+            .max(TRACK_MIN_STEP_SECONDS)
+            .max(horizon / TRACK_MAX_SAMPLES)
+            .round();
+        (Unit::Second * step, (horizon / step).ceil() as usize)
+    }
+
+    /// simulation time, clock state and element freshness (by the wall clock)
+    pub fn title(&self, time: Epoch) -> String {
+        let mut clock = format!("{} [{}]", time, self.clock.describe());
+        if self.projection != Projection::Equirectangular {
+            clock.push_str(&format!(" [{}]", self.projection.name()));
+        }
+        if self.viewport.zoom > 1.0 {
+            clock.push_str(&format!(" [zoom ×{:.1}]", self.viewport.zoom));
+        }
+        if self.follow {
+            clock.push_str(" [following]");
+        }
+        if self.track_orbits != 1.0 {
+            clock.push_str(&format!(" [track {} orbits]", self.track_orbits));
+        }
+        match self.freshness.describe(Epoch::now().unwrap()) {
+            Some(status) => format!("{} ({})", clock, status),
+            None => clock,
+        }
+    }
+
+    /// Brings the cached ground tracks of the shown satellites up to `time`, propagating
+    /// only the samples that came into range. A new sample spacing, after zooming or `(`
+    /// `)`, starts a satellite's samples over.
+    pub fn update_tracks(&mut self, time: Epoch) {
+        let eop = self.args.earth_orientation;
+        let satellites = self.satellites.clone();
+        // one sun position per sample time, however many satellites are sampled then
+        let mut suns: HashMap<i64, [f64; 3]> = HashMap::new();
+        for sat in satellites.iter() {
+            let Ok(propagator) = &sat.propagator else {
+                continue;
+            };
+            if !search_match(&self.search, &sat.elements) {
+                continue;
+            }
+            // the propagator refuses a mean motion without a period, so the fallback is never used
+            let (step, steps) = self.track_sampling(sat.period().unwrap_or(Unit::Day * 1));
+            let step = step.to_seconds() as i64;
+            let samples = self
+                .tracks
+                .entry(sat.elements.norad_id)
+                .or_insert_with(|| TrackSamples::new(step));
+            if samples.step != step {
+                *samples = TrackSamples::new(step);
+            }
+            let index = samples.index(time);
+            let steps = steps as i64;
+            let low = if self.show_past_track {
+                index + 1 - steps
+            } else {
+                index + 1
+            };
+            samples.cover(low, index + steps, |time| {
+                let sun = *suns
+                    .entry((time.to_tai_seconds()).round() as i64)
+                    .or_insert_with(|| sun_position(time));
+                track_point(time, propagator, &eop, sun).ok()
+            });
+        }
+    }
+
+    /// Positions of the shown satellites at `time`, with the ground tracks cached by
+    /// `update_tracks` for the same time
+    pub fn snapshot(&self, time: Epoch) -> Snapshot<'_> {
+        let eop = &self.args.earth_orientation;
+        let sun = sun_position(time);
+
+        let mut tracked = Vec::new();
+        let mut lost = Vec::new();
+        for sat in self.shown_satellites() {
+            let propagator = match &sat.propagator {
+                Ok(propagator) => propagator,
+                Err(error) => {
+                    lost.push((sat, error.clone()));
+                    continue;
+                }
+            };
+            let prediction = match propagator.propagate(time) {
+                Ok(prediction) => prediction,
+                Err(error) => {
+                    lost.push((sat, error));
+                    continue;
+                }
+            };
+            let ecef = teme_to_ecef_state(prediction.position, prediction.velocity, time, eop);
+            let position = ecef_to_geodetic(ecef.position);
+            let light = illumination(prediction.position, sun);
+            let now = TrackPoint {
+                time,
+                pos: position,
+                light,
+            };
+            let (track, past) = match self.tracks.get(&sat.elements.norad_id) {
+                Some(samples) => {
+                    let steps = self.track_sampling(sat.period().unwrap_or(Unit::Day * 1)).1 as i64;
+                    let index = samples.index(time);
+                    // a track that fails part way (e.g. decaying) is drawn up to the failure
+                    let track = std::iter::once(now)
+                        .chain((index + 1..=index + steps).map_while(|i| samples.get(i)))
+                        .collect();
+                    let mut past: Vec<TrackPoint> = Vec::new();
+                    if self.show_past_track {
+                        past.extend(
+                            (index + 1 - steps..=index)
+                                .rev()
+                                .map_while(|i| samples.get(i)),
+                        );
+                        past.reverse();
+                        past.push(now);
+                    }
+                    (track, past)
+                }
+                None => (vec![now], Vec::new()),
+            };
+            let look = self
+                .observer
+                .as_ref()
+                .map(|observer| observer.look_angles(&ecef));
+            tracked.push(Tracked {
+                sat,
+                position,
+                light,
+                speed: prediction
+                    .velocity
+                    .iter()
+                    .map(|v| v.powi(2))
+                    .sum::<f64>()
+                    .sqrt(),
+                track,
+                past,
+                look,
+            });
+        }
+        let shown = tracked
+            .iter()
+            .map(|tracked| tracked.sat)
+            .chain(lost.iter().map(|(sat, _)| *sat))
+            .map(|sat| sat.elements.norad_id)
+            .collect();
+        Snapshot {
+            time,
+            subsolar: subsolar_point(time, eop),
+            tracked,
+            lost,
+            shown,
+        }
+    }
+}
+
+/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L79-L94
+pub fn track_point(
+    time: Epoch,
+    propagator: &Propagator,
+    eop: &EarthOrientation,
+    sun: [f64; 3],
+) -> Result<TrackPoint, PropagationError> {
+    let prediction = propagator.propagate(time)?;
+    Ok(TrackPoint {
+        time,
+        pos: teme_to_geodetic(prediction.position, time, eop),
+        light: illumination(prediction.position, sun),
+    })
+}
+
+/// keeps the selected element sets and builds their propagators
+fn track(elements_vec: Vec<Elements>, selection: &Selection) -> Vec<Satellite> {
+    elements_vec
+        .into_iter()
+        .filter(|elements| selection.matches(elements))
+        .map(Satellite::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kuiper(norad_id: u64, epoch: &str) -> Elements {
+        let line1 = format!(
+            "1 {}U 23154A   {}  .00001000  00000-0  50000-4 0  999",
+            norad_id, epoch
+        );
+        let line2 = format!(
+            "2 {}  51.9000 120.0000 0001000  90.0000 270.0000 15.20000000 1000",
+            norad_id
+        );
+        // sgp4 checks the checksums, so they are appended rather than written out
+        let checksum = |line: &str| {
+            let sum: u32 = line
+                .chars()
+                .map(|c| c.to_digit(10).unwrap_or(u32::from(c == '-')))
+                .sum();
+            format!("{}{}", line, sum % 10)
+        };
+        Elements::from_tle(
+            Some(format!("KUIPER-{}", norad_id)),
+            checksum(&line1).as_bytes(),
+            checksum(&line2).as_bytes(),
+        )
+        .unwrap()
+    }
+
+    /// an app for a local element file, so every element set is taken
+    fn app(elements: Vec<Elements>) -> App {
+        let args = Args::parse_from(["-e", "kuiper.tle"].map(String::from)).unwrap();
+        let mut app = App::new(args);
+        app.load(
+            elements,
+            Epoch::from_gregorian_utc_at_midnight(2026, 10, 16),
+        );
+        app
+    }
+
+    #[test]
+    fn snapshot_keeps_others_when_one_fails() {
+        // epochs 2026-10-15 and, far too old for the snapshot, 2026-06-02
+        let app = app(vec![
+            kuiper(58012, "26288.50000000"),
+            kuiper(58013, "26153.00000000"),
+            kuiper(58014, "26288.50000000"),
+        ]);
+        let snapshot = app.snapshot(midnight());
+        let tracked: Vec<u64> = snapshot
+            .tracked
+            .iter()
+            .map(|tracked| tracked.sat.elements.norad_id)
+            .collect();
+        assert_eq!(tracked, [58012, 58014]);
+        assert_eq!(snapshot.lost.len(), 1);
+        assert_eq!(snapshot.lost[0].0.elements.norad_id, 58013);
+        assert!(matches!(
+            snapshot.lost[0].1,
+            PropagationError::EpochTooFar { .. }
+        ));
+        assert_eq!(snapshot.shown.len(), 3);
+    }
+
+    fn midnight() -> Epoch {
+        Epoch::from_gregorian_utc_at_midnight(2026, 10, 16)
+    }
+
+    /// updates the predictions until the worker has answered the last request
+    fn settle(app: &mut App, time: Epoch) {
+        app.update_predictions(time);
+        for _ in 0..1000 {
+            if !app.predicting() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            app.update_predictions(time);
+        }
+        panic!("no predictions for {}", time);
+    }
+
+    #[test]
+    fn prediction_window_follows_the_clock() {
+        let mut app = app(vec![kuiper(58012, "26288.50000000")]);
+        // --days 2 ahead and behind, moved once the clock is a day from the middle
+        let day = Unit::Day * 1;
+        let window = |time: Epoch| Some((time - day * 2, time + day * 2));
+        let start = midnight();
+        app.update_predictions(start);
+        assert_eq!(app.requested, window(start));
+        assert_eq!(app.predicted, None);
+        settle(&mut app, start);
+        assert_eq!(app.requested, None);
+        assert_eq!(app.predicted, window(start));
+        assert!(!app.eclipses.is_empty());
+
+        app.update_predictions(start + day * 0.9);
+        assert_eq!(app.requested, None);
+        let later = start + day * 1.1;
+        app.update_predictions(later);
+        assert_eq!(app.requested, window(later));
+        assert_eq!(app.predicted, window(start));
+        settle(&mut app, later);
+        assert_eq!(app.predicted, window(later));
+
+        // backwards just the same
+        app.update_predictions(later - day * 0.9);
+        assert_eq!(app.requested, None);
+        let earlier = later - day * 1.5;
+        app.update_predictions(earlier);
+        assert_eq!(app.requested, window(earlier));
+        settle(&mut app, earlier);
+        assert_eq!(app.predicted, window(earlier));
+    }
+
+    #[test]
+    fn new_elements_drop_predictions_in_flight() {
+        let mut app = app(vec![kuiper(58012, "26288.50000000")]);
+        let start = midnight();
+        app.update_predictions(start);
+        let first = app.generation;
+        let in_flight = app.requested;
+        assert!(in_flight.is_some());
+
+        app.load(vec![kuiper(58013, "26288.50000000")], start);
+        assert_eq!(app.generation, first + 1);
+        assert_eq!(app.requested, None);
+        assert_eq!(app.predicted, None);
+        // the answer for the replaced elements is ignored
+        app.receive(Predictions {
+            generation: first,
+            window: in_flight.unwrap(),
+            passes: Vec::new(),
+            eclipses: Vec::new(),
+        });
+        assert_eq!(app.predicted, None);
+
+        let later = start + Unit::Hour * 1;
+        settle(&mut app, later);
+        assert_eq!(
+            app.predicted,
+            Some((later - Unit::Day * 2, later + Unit::Day * 2))
+        );
+        assert!(app.eclipses.iter().all(|event| event.norad_id == 58013));
+    }
+
+    #[test]
+    fn epoch_notice_wording() {
+        let app = app(vec![
+            kuiper(58012, "26288.50000000"),
+            kuiper(58013, "26153.00000000"),
+            kuiper(58014, "26350.00000000"),
+        ]);
+        assert_eq!(
+            app.snapshot(midnight()).epoch_notice().as_deref(),
+            Some(
+                "1 element sets too old, 1 element sets too new for 2026-10-16 00:00:00 \
+                 (over 30 days from their epoch)"
+            )
+        );
+        let app = self::app(vec![kuiper(58012, "26288.50000000")]);
+        assert_eq!(app.snapshot(midnight()).epoch_notice(), None);
+    }
+
+    #[test]
+    fn track_samples_slide_with_the_clock() {
+        let mut samples = TrackSamples::new(10);
+        let mut propagated = Vec::new();
+        let mut cover = |samples: &mut TrackSamples, low, high| {
+            samples.cover(low, high, |time| {
+                propagated.push(time);
+                Some(TrackPoint {
+                    time,
+                    pos: GroundPos {
+                        lat: 0.0,
+                        lon: 0.0,
+                        alt: 0.0,
+                    },
+                    light: Illumination::Sunlit,
+                })
+            });
+        };
+        cover(&mut samples, 0, 5);
+        cover(&mut samples, 2, 7);
+        cover(&mut samples, -1, 4);
+        assert_eq!(samples.first, -1);
+        assert_eq!(samples.points.len(), 6);
+        cover(&mut samples, 100, 101);
+        let seconds: Vec<f64> = propagated
+            .iter()
+            .map(|time| time.to_tai_seconds())
+            .collect();
+        // 0 to 5, then 6 and 7 later, then -1 back to 1 before, then a fresh start
+        assert_eq!(
+            seconds,
+            [0, 10, 20, 30, 40, 50, 60, 70, 10, 0, -10, 1000, 1010].map(f64::from)
+        );
+        let time = Epoch::from_tai_seconds(1005.0);
+        assert_eq!(samples.index(time), 100);
+        assert_eq!(
+            samples.get(101).map(|point| point.time),
+            Some(samples.time(101))
+        );
+        assert!(samples.get(102).is_none());
+    }
+}
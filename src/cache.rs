@@ -0,0 +1,194 @@
+use anyhow::Context;
+use hifitime::prelude::*;
+use serde::{Deserialize, Serialize};
+use sgp4::Elements;
+use std::{fs, path::PathBuf};
+
+/// On-disk copy of the last successful element fetch
+#[derive(Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    key: String,
+}
+
+/// Elements read back from the cache together with when they were fetched
+pub struct CachedElements {
+    pub fetched_at: Epoch,
+    pub elements: Vec<Elements>,
+}
+
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+    fetched_at_unix: f64,
+    elements: &'a [Elements],
+}
+
+#[derive(Deserialize)]
+struct CacheFile {
+    fetched_at_unix: f64,
+    elements: Vec<Elements>,
+}
+
+impl Cache {
+    /// `key` names the cache file, so different queries don't overwrite each other
+    pub fn new(dir: impl Into<PathBuf>, key: impl Into<String>) -> Self {
+        Cache {
+            dir: dir.into(),
+            key: key.into(),
+        }
+    }
+
+    /// $XDG_CACHE_HOME/tuiper, falling back to ~/.cache/tuiper
+    pub fn default_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .map(|dir| dir.join("tuiper"))
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", self.key))
+    }
+
+    /// returns `Ok(None)` if nothing has been cached yet
+    pub fn load(&self) -> anyhow::Result<Option<CachedElements>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let file: CacheFile = serde_json::from_str(&text)
+            .with_context(|| format!("parsing cache file {}", path.display()))?;
+        Ok(Some(CachedElements {
+            fetched_at: Epoch::from_unix_seconds(file.fetched_at_unix),
+            elements: file.elements,
+        }))
+    }
+
+    /// writes to a temporary file first so a crash never leaves a truncated cache behind
+    pub fn store(&self, elements: &[Elements], fetched_at: Epoch) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating cache directory {}", self.dir.display()))?;
+        let path = self.path();
+        let tmp = path.with_extension("json.tmp");
+        let text = serde_json::to_string(&CacheFileRef {
+            fetched_at_unix: fetched_at.to_unix_seconds(),
+            elements,
+        })?;
+        fs::write(&tmp, text).with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// How current the elements on screen are
+pub enum Freshness {
+    /// nothing loaded yet, first fetch from the named source in flight
+    Fetching(String),
+    /// nothing loaded and the fetch failed
+    Failed(String),
+    /// freshly fetched or loaded from a local file
+    Live,
+    /// served from the cache, possibly because the refresh failed
+    Cached {
+        fetched_at: Epoch,
+        refresh_failed: bool,
+    },
+}
+
+impl Freshness {
+    /// suffix for the title bar, `None` when the elements are current
+    pub fn describe(&self, now: Epoch) -> Option<String> {
+        match self {
+            Freshness::Fetching(source) => Some(format!("fetching orbits from {}...", source)),
+            Freshness::Failed(error) => Some(format!("fetch failed: {}", error)),
+            Freshness::Live => None,
+            Freshness::Cached {
+                fetched_at,
+                refresh_failed,
+            } => {
+                let hours = (now - *fetched_at).to_unit(Unit::Hour);
+                let mut description = format!("cached, {:.1} hours old", hours);
+                if *refresh_failed {
+                    description.push_str(", refresh failed");
+                }
+                Some(description)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tuiper-cache-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = temp_dir("round-trip");
+        let cache = Cache::new(&dir, "celestrak_intdes-2023-154");
+        let elements = Elements::from_tle(
+            Some("KUIPER-P1".to_string()),
+            b"1 58012U 23154A   26288.50000000  .00001000  00000-0  50000-4 0  9992",
+            b"2 58012  51.9000 120.0000 0001000  90.0000 270.0000 15.20000000 10004",
+        )
+        .unwrap();
+        let fetched_at = Epoch::from_gregorian_utc(2026, 10, 15, 12, 30, 15, 250_000_000);
+
+        let empty = cache.load();
+        let stored = cache.store(&[elements], fetched_at);
+        let loaded = cache.load();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(empty.unwrap().is_none());
+        stored.unwrap();
+        let loaded = loaded.unwrap().unwrap();
+        assert!((loaded.fetched_at - fetched_at).abs() < Unit::Millisecond * 1);
+        assert_eq!(loaded.elements.len(), 1);
+        assert_eq!(loaded.elements[0].norad_id, 58012);
+        assert_eq!(loaded.elements[0].object_name.as_deref(), Some("KUIPER-P1"));
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let dir = temp_dir("corrupt");
+        let cache = Cache::new(&dir, "broken");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("broken.json"), "{\"fetched_at_unix\": 17").unwrap();
+        let loaded = cache.load();
+        fs::remove_dir_all(&dir).unwrap();
+        let error = loaded.err().unwrap();
+        assert!(error.to_string().starts_with("parsing cache file"));
+    }
+
+    #[test]
+    fn describe_freshness() {
+        let now = Epoch::from_gregorian_utc_at_midnight(2026, 10, 16);
+        assert_eq!(Freshness::Live.describe(now), None);
+        assert_eq!(
+            Freshness::Fetching("celestrak".to_string()).describe(now),
+            Some("fetching orbits from celestrak...".to_string())
+        );
+        assert_eq!(
+            Freshness::Failed("timed out".to_string()).describe(now),
+            Some("fetch failed: timed out".to_string())
+        );
+        let cached = |refresh_failed| Freshness::Cached {
+            fetched_at: now - Unit::Minute * 90,
+            refresh_failed,
+        };
+        assert_eq!(
+            cached(false).describe(now),
+            Some("cached, 1.5 hours old".to_string())
+        );
+        assert_eq!(
+            cached(true).describe(now),
+            Some("cached, 1.5 hours old, refresh failed".to_string())
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use crate::{
+    clock::parse_utc,
+    frames::{EarthOrientation, GroundPos},
+    oem::Frame,
+    positions::Format,
+    projection::Projection,
+    selection::Selection,
+    source::ElementSource,
+    tracks::TrackFormat,
+    visibility::{StandardMagnitudes, KUIPER_STANDARD_MAGNITUDE},
+};
+use anyhow::{bail, Context};
+use hifitime::Epoch;
+use regex::RegexBuilder;
+use std::{path::PathBuf, time::Duration};
+
+/// CelesTrak only updates most element sets a few times a day
+const DEFAULT_REFRESH_MINUTES: u64 = 120;
+
+pub const USAGE: &str = "\
+usage: tuiper [options]
+
+options:
+  -e, --elements <PATH>   load elements from an OMM JSON file, a TLE file or a
+                          directory of them instead of querying celestrak
+
+selection (repeatable, comma separated lists allowed; default: everything in
+an --elements file, or from celestrak the Kuiper prototypes, --intdes 2023-154
+--name 'KUIPER*'):
+  -g, --group <NAME>      celestrak GROUP, e.g. active or starlink
+  -i, --intdes <DESIG>    international designator or launch, e.g. 2023-154
+  -n, --norad <ID>        NORAD catalog number
+      --name <GLOB>       object name glob, e.g. 'KUIPER-*' or 'KUIPER-P[12]'
+      --name-regex <RE>   object name regular expression, ignoring case, e.g.
+                          '^KUIPER-\\d{5}$' (not split at commas)
+
+cache:
+      --cache-dir <PATH>  where to keep the last celestrak response
+                          (default: $XDG_CACHE_HOME/tuiper or ~/.cache/tuiper)
+      --no-cache          neither read nor write the element cache
+  -r, --refresh <MIN>     reload elements every MIN minutes, 0 to never
+                          reload (default: 120)
+
+observer:
+  -o, --observer <LAT,LON[,ALT]>
+                          ground station in degrees, altitude in meters
+                          above the WGS-84 ellipsoid; shows look angles
+                          and upcoming passes
+      --days <N>          how many days ahead to predict passes (default: 2)
+      --min-elevation <DEG>
+                          elevation mask for passes (default: 10)
+      --passes            print the upcoming passes and exit
+      --visible           only list passes where the satellite is sunlit
+                          while the observer is in darkness
+      --std-mag <MAG|NORAD=MAG>
+                          standard magnitude (at 1000 km, half lit) to
+                          estimate pass brightness with, for every satellite
+                          or one NORAD ID, e.g. 25544=-1.8,6 (default: 5 for
+                          the Kuiper prototypes, otherwise none)
+
+map:
+      --projection <NAME> equirectangular (default), mercator, globe, north
+                          or south (polar views)
+      --footprint-elevation <DEG>
+                          minimum user elevation the coverage footprints
+                          are drawn for (default: 35, as for Kuiper user
+                          terminals)
+      --track-orbits <N>  how many orbits of ground track to draw, from each
+                          satellite's mean motion (default: 1)
+      --track-points <N>  ground track points per orbit (default: 90, more
+                          when zoomed in)
+      --globe-center <LAT,LON>
+                          where the globe is seen from (default: the
+                          observer, or 0,0)
+
+output:
+      --positions         print where the satellites are and exit
+      --at <UTC>          time for --positions, --oem and --tracks, e.g.
+                          '2024-05-01 12:00' (default: now)
+      --format <FORMAT>   table (default), json or csv
+      --oem <PATH>        write state vectors from --at or now as a CCSDS
+                          orbit ephemeris message and exit; XML if PATH
+                          ends in .xml, KVN otherwise
+      --frame <NAME>      reference frame for --oem: eme2000 (default),
+                          teme or itrf
+      --span <HOURS>      how long --oem covers (default: 24)
+      --step <SECONDS>    time between --oem states (default: 60)
+      --tracks <PATH>     write the ground tracks drawn on the map, from --at
+                          or now, and exit; KML with time stamps if PATH
+                          ends in .kml, GeoJSON if it ends in .geojson or
+                          .json
+
+earth orientation:
+      --ut1-utc <SECONDS> UT1 - UTC from IERS Bulletin A (default: 0)
+      --polar-motion <XP,YP>
+                          polar motion in arcseconds from IERS Bulletin A
+                          (default: 0,0)
+
+  -h, --help              print this help
+
+keys:
+  q                       quit
+  v                       switch between the map and the sky plot
+  n                       show / hide night and twilight on the map
+  up down, [ ]            select the previous / next satellite
+  esc                     clear the selection
+  t                       show / hide the satellite table
+  /                       search by name, NORAD ID or designator; narrows
+                          the table and the map as you type
+  p                       show / hide the pass table
+  s S                     change / reverse the pass table order
+  o                       list only optically visible passes, or all
+  space                   pause / resume the clock
+  + -                     run the clock faster / slower, down to backwards
+  , .                     step the clock back / forward a minute
+  < >                     step the clock back / forward an hour
+  g                       jump to a UTC date and time
+  l                       back to live time
+  z Z, mouse wheel        zoom the map in / out
+  H J K L, mouse drag     pan the map west / south / north / east
+  f                       follow the selected satellite
+  0                       show the whole map again
+  m                       switch map projection; H J K L turn the globe
+  c                       show / hide coverage footprints
+  b                       show / hide the ground track already flown
+  { }                     draw half an orbit less / more of ground track
+  ( )                     draw ground tracks coarser / finer
+";
+
+/// Command line arguments
+pub struct Args {
+    pub source: ElementSource,
+    pub selection: Selection,
+    pub cache_dir: Option<PathBuf>,
+    pub no_cache: bool,
+    pub refresh_interval: Option<Duration>,
+    pub earth_orientation: EarthOrientation,
+    pub observer: Option<GroundPos>,
+    pub pass_days: f64,
+    pub min_elevation: f64,
+    /// user terminal elevation mask for coverage footprints, in degrees
+    pub footprint_elevation: f64,
+    /// ground track length in orbits
+    pub track_orbits: f64,
+    /// ground track samples per orbit at zoom 1
+    pub track_points: f64,
+    pub print_passes: bool,
+    pub print_positions: bool,
+    /// time to print positions for instead of now
+    pub at: Option<Epoch>,
+    pub format: Format,
+    pub oem_path: Option<PathBuf>,
+    pub tracks_path: Option<(PathBuf, TrackFormat)>,
+    pub frame: Frame,
+    /// hours of ephemeris to export
+    pub span: f64,
+    /// seconds between exported states
+    pub step: f64,
+    pub visible_only: bool,
+    pub standard_magnitudes: StandardMagnitudes,
+    pub projection: Projection,
+    /// (lat, lon) the globe projection starts out centered on
+    pub globe_center: (f64, f64),
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse() -> anyhow::Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut elements_path = None;
+        let mut selection = Selection::default();
+        let mut parsed = Args {
+            source: ElementSource::Celestrak(Vec::new()),
+            selection: Selection::default(),
+            cache_dir: None,
+            no_cache: false,
+            refresh_interval: Some(Duration::from_secs(DEFAULT_REFRESH_MINUTES * 60)),
+            earth_orientation: EarthOrientation::default(),
+            observer: None,
+            pass_days: 2.0,
+            min_elevation: 10.0,
+            footprint_elevation: 35.0,
+            track_orbits: 1.0,
+            track_points: 90.0,
+            print_passes: false,
+            print_positions: false,
+            at: None,
+            format: Format::Table,
+            oem_path: None,
+            tracks_path: None,
+            frame: Frame::Eme2000,
+            span: 24.0,
+            step: 60.0,
+            visible_only: false,
+            standard_magnitudes: StandardMagnitudes::default(),
+            projection: Projection::Equirectangular,
+            globe_center: (0.0, 0.0),
+            help: false,
+        };
+        let mut projection = None;
+        let mut globe_center = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-e" | "--elements" => {
+                    let path = args.next().context("--elements needs a path")?;
+                    elements_path = Some(PathBuf::from(path));
+                }
+                "-g" | "--group" => {
+                    let groups = args.next().context("--group needs a name")?;
+                    selection.groups.extend(split_list(&groups));
+                }
+                "-i" | "--intdes" => {
+                    let intdes = args.next().context("--intdes needs a designator")?;
+                    selection.intdes.extend(split_list(&intdes));
+                }
+                "-n" | "--norad" => {
+                    let ids = args.next().context("--norad needs a catalog number")?;
+                    for id in split_list(&ids) {
+                        let id = id
+                            .parse()
+                            .with_context(|| format!("'{}' is not a NORAD catalog number", id))?;
+                        selection.norad_ids.push(id);
+                    }
+                }
+                "--name" => {
+                    let names = args.next().context("--name needs a pattern")?;
+                    selection.names.extend(split_list(&names));
+                }
+                "--name-regex" => {
+                    let pattern = args.next().context("--name-regex needs a pattern")?;
+                    let regex = RegexBuilder::new(&pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .with_context(|| format!("'{}' is not a regular expression", pattern))?;
+                    selection.name_regexes.push(regex);
+                }
+                "--cache-dir" => {
+                    let path = args.next().context("--cache-dir needs a path")?;
+                    parsed.cache_dir = Some(PathBuf::from(path));
+                }
+                "--no-cache" => parsed.no_cache = true,
+                "-r" | "--refresh" => {
+                    let minutes = args.next().context("--refresh needs a number of minutes")?;
+                    let minutes = parse_number(&minutes)?;
+                    parsed.refresh_interval =
+                        (minutes > 0.0).then(|| Duration::from_secs_f64(minutes * 60.0));
+                }
+                "-o" | "--observer" => {
+                    let location = args.next().context("--observer needs LAT,LON[,ALT]")?;
+                    parsed.observer = Some(parse_location(&location)?);
+                }
+                "--days" => {
+                    let days = args.next().context("--days needs a number of days")?;
+                    parsed.pass_days = parse_number(&days)?;
+                    if parsed.pass_days <= 0.0 {
+                        bail!("--days must be positive");
+                    }
+                }
+                "--min-elevation" => {
+                    let elevation = args.next().context("--min-elevation needs degrees")?;
+                    parsed.min_elevation = parse_number(&elevation)?;
+                }
+                "--footprint-elevation" => {
+                    let elevation = args.next().context("--footprint-elevation needs degrees")?;
+                    parsed.footprint_elevation = parse_number(&elevation)?;
+                    if !(0.0..90.0).contains(&parsed.footprint_elevation) {
+                        bail!("--footprint-elevation must be from 0 up to 90 degrees");
+                    }
+                }
+                "--track-orbits" => {
+                    let orbits = args.next().context("--track-orbits needs a number")?;
+                    parsed.track_orbits = parse_number(&orbits)?;
+                    if parsed.track_orbits <= 0.0 {
+                        bail!("--track-orbits must be positive");
+                    }
+                }
+                "--track-points" => {
+                    let points = args.next().context("--track-points needs a number")?;
+                    parsed.track_points = parse_number(&points)?;
+                    if parsed.track_points < 2.0 {
+                        bail!("--track-points must be at least 2");
+                    }
+                }
+                "--passes" => parsed.print_passes = true,
+                "--visible" => parsed.visible_only = true,
+                "--std-mag" => {
+                    let list = args.next().context("--std-mag needs MAG or NORAD=MAG")?;
+                    for item in split_list(&list) {
+                        let magnitudes = &mut parsed.standard_magnitudes;
+                        match item.split_once('=') {
+                            Some((norad_id, magnitude)) => {
+                                let norad_id = norad_id
+                                    .trim()
+                                    .parse()
+                                    .with_context(|| format!("'{}' is not a NORAD ID", norad_id))?;
+                                magnitudes
+                                    .by_norad
+                                    .push((norad_id, parse_number(magnitude)?));
+                            }
+                            None => magnitudes.fallback = Some(parse_number(&item)?),
+                        }
+                    }
+                }
+                "--positions" => parsed.print_positions = true,
+                "--at" => {
+                    let time = args.next().context("--at needs a UTC date and time")?;
+                    parsed.at = Some(parse_utc(&time)?);
+                }
+                "--format" => {
+                    let name = args.next().context("--format needs a name")?;
+                    parsed.format = Format::from_name(&name)
+                        .with_context(|| format!("unknown format '{}'", name))?;
+                }
+                "--oem" => {
+                    let path = args.next().context("--oem needs a path")?;
+                    parsed.oem_path = Some(PathBuf::from(path));
+                }
+                "--tracks" => {
+                    let path = PathBuf::from(args.next().context("--tracks needs a path")?);
+                    let format = path
+                        .extension()
+                        .and_then(|extension| {
+                            TrackFormat::from_extension(&extension.to_string_lossy())
+                        })
+                        .with_context(|| {
+                            format!(
+                                "--tracks needs a .kml or .geojson path, got '{}'",
+                                path.display()
+                            )
+                        })?;
+                    parsed.tracks_path = Some((path, format));
+                }
+                "--frame" => {
+                    let name = args.next().context("--frame needs a name")?;
+                    parsed.frame = Frame::from_name(&name)
+                        .with_context(|| format!("unknown frame '{}'", name))?;
+                }
+                "--span" => {
+                    let hours = args.next().context("--span needs a number of hours")?;
+                    parsed.span = parse_number(&hours)?;
+                    if parsed.span <= 0.0 {
+                        bail!("--span must be positive");
+                    }
+                }
+                "--step" => {
+                    let seconds = args.next().context("--step needs a number of seconds")?;
+                    parsed.step = parse_number(&seconds)?;
+                    if parsed.step <= 0.0 {
+                        bail!("--step must be positive");
+                    }
+                }
+                "--ut1-utc" => {
+                    let seconds = args.next().context("--ut1-utc needs a number of seconds")?;
+                    parsed.earth_orientation.ut1_utc = parse_number(&seconds)?;
+                }
+                "--polar-motion" => {
+                    let values = args.next().context("--polar-motion needs XP,YP")?;
+                    let Some((xp, yp)) = values.split_once(',') else {
+                        bail!("--polar-motion needs XP,YP, got '{}'", values);
+                    };
+                    parsed.earth_orientation.xp = parse_number(xp)?;
+                    parsed.earth_orientation.yp = parse_number(yp)?;
+                }
+                "--projection" => {
+                    projection = Some(args.next().context("--projection needs a name")?);
+                }
+                "--globe-center" => {
+                    let center = args.next().context("--globe-center needs LAT,LON")?;
+                    globe_center = Some(parse_location(&center)?);
+                }
+                "-h" | "--help" => parsed.help = true,
+                other => bail!("unknown argument '{}'\n\n{}", other, USAGE),
+            }
+        }
+
+        if let Some(center) = globe_center.or(parsed.observer) {
+            parsed.globe_center = (center.lat, center.lon);
+        }
+        if let Some(name) = projection {
+            parsed.projection = Projection::from_name(&name, parsed.globe_center)
+                .with_context(|| format!("unknown projection '{}'", name))?;
+        } else if globe_center.is_some() {
+            parsed.projection = Projection::from_name("globe", parsed.globe_center).unwrap();
+        }
+
+        // a local file is already a choice of satellites, so it is taken whole
+        parsed.selection = if selection.is_empty() && elements_path.is_none() {
+            let fallback = &mut parsed.standard_magnitudes.fallback;
+            fallback.get_or_insert(KUIPER_STANDARD_MAGNITUDE);
+            Selection::kuiper_protosats()
+        } else {
+            selection
+        };
+        parsed.source = match elements_path {
+            Some(path) => ElementSource::from_path(path),
+            None => ElementSource::Celestrak(parsed.selection.celestrak_queries()),
+        };
+        Ok(parsed)
+    }
+}
+
+/// "LAT,LON[,ALT]" in degrees and meters
+fn parse_location(text: &str) -> anyhow::Result<GroundPos> {
+    let parts: Vec<&str> = text.split(',').collect();
+    if !(2..=3).contains(&parts.len()) {
+        bail!("expected LAT,LON[,ALT], got '{}'", text);
+    }
+    let lat = parse_number(parts[0])?;
+    let lon = parse_number(parts[1])?;
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=360.0).contains(&lon) {
+        bail!("'{}' is not a valid latitude, longitude", text);
+    }
+    let alt = parts.get(2).map_or(Ok(0.0), |alt| parse_number(alt))? / 1000.0;
+    Ok(GroundPos {
+        lat,
+        lon: if lon > 180.0 { lon - 360.0 } else { lon },
+        alt,
+    })
+}
+
+fn parse_number(text: &str) -> anyhow::Result<f64> {
+    text.trim()
+        .parse()
+        .with_context(|| format!("'{}' is not a number", text))
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::parse_from(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn kuiper_default_only_for_celestrak() {
+        assert_eq!(parse(&[]).selection.intdes, ["2023-154"]);
+        assert!(parse(&["-e", "iss.tle"]).selection.is_empty());
+        assert_eq!(
+            parse(&["-e", "iss.tle", "--name", "ISS*"]).selection.names,
+            ["ISS*"]
+        );
+    }
+
+    #[test]
+    fn standard_magnitudes() {
+        assert_eq!(
+            parse(&[]).standard_magnitudes.fallback,
+            Some(KUIPER_STANDARD_MAGNITUDE)
+        );
+        assert_eq!(
+            parse(&["-g", "stations"]).standard_magnitudes.fallback,
+            None
+        );
+        let magnitudes = parse(&["--std-mag", "25544=-1.8, 6"]).standard_magnitudes;
+        assert_eq!(magnitudes.by_norad, [(25544, -1.8)]);
+        assert_eq!(magnitudes.fallback, Some(6.0));
+        assert!(Args::parse_from(["--std-mag".to_string(), "ISS=1".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use core::str::FromStr;
+use hifitime::prelude::*;
+
+/// "2024-01-31 23:59:59" in UTC, for tables where the full hifitime format is too wide
+pub fn format_utc(time: Epoch) -> String {
+    let (year, month, day, hour, minute, second, _) = time.to_gregorian_utc();
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// "YYYY-MM-DDTHH:MM:SSZ", ISO 8601 for other programs
+pub fn format_iso(time: Epoch) -> String {
+    format_utc(time).replace(' ', "T") + "Z"
+}
+
+/// "HH:MM:SS" in UTC
+pub fn format_utc_time(time: Epoch) -> String {
+    let (_, _, _, hour, minute, second, _) = time.to_gregorian_utc();
+    format!("{:02}:{:02}:{:02}", hour, minute, second)
+}
+
+/// "1h02m" / "4m05s"
+pub fn format_span(span: Duration) -> String {
+    let seconds = span.to_seconds().abs().round() as u64;
+    if seconds >= 3600 {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+/// speed multipliers stepped through with `+` / `-`, negative runs the clock backwards
+const SPEEDS: [f64; 12] = [
+    -3600.0, -600.0, -60.0, -10.0, -1.0, 1.0, 10.0, 60.0, 600.0, 3600.0, 21600.0, 86400.0,
+];
+
+/// Simulation time: follows the wall clock at some multiple of real time and can be
+/// paused, stepped or moved to any instant
+pub struct SimClock {
+    /// simulation time at the wall clock instant `anchor`
+    base: Epoch,
+    anchor: Epoch,
+    speed: f64,
+    paused: bool,
+}
+
+impl SimClock {
+    /// running at real time, starting now
+    pub fn live() -> Self {
+        let now = Epoch::now().unwrap();
+        SimClock {
+            base: now,
+            anchor: now,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn now(&self) -> Epoch {
+        if self.paused {
+            self.base
+        } else {
+            self.base + (Epoch::now().unwrap() - self.anchor) * self.speed
+        }
+    }
+
+    /// whether this is simply the current time
+    pub fn is_live(&self) -> bool {
+        !self.paused
+            && self.speed == 1.0
+            && (self.now() - Epoch::now().unwrap()).abs() < Unit::Second * 1
+    }
+
+    /// restarts the wall clock reference so speed changes apply from now on
+    fn rebase(&mut self) {
+        self.base = self.now();
+        self.anchor = Epoch::now().unwrap();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.rebase();
+        self.paused = !self.paused;
+    }
+
+    /// moves `steps` places along the speed ladder, e.g. 1 from ×1 to ×10 or -1 from ×1 to ×-1
+    pub fn change_speed(&mut self, steps: isize) {
+        self.rebase();
+        let current = SPEEDS
+            .iter()
+            .position(|&speed| speed == self.speed)
+            .unwrap_or(SPEEDS.len() / 2) as isize;
+        let index = (current + steps).clamp(0, SPEEDS.len() as isize - 1);
+        self.speed = SPEEDS[index as usize];
+    }
+
+    pub fn step(&mut self, by: Duration) {
+        self.rebase();
+        self.base += by;
+    }
+
+    pub fn jump(&mut self, to: Epoch) {
+        self.rebase();
+        self.base = to;
+    }
+
+    /// "live", "paused", "×60", "×-10"
+    pub fn describe(&self) -> String {
+        if self.is_live() {
+            "live".to_string()
+        } else if self.paused {
+            "paused".to_string()
+        } else {
+            format!("×{}", self.speed)
+        }
+    }
+}
+
+/// parses "2024-01-31", "2024-01-31 23:59" or "2024-01-31T23:59:59" as UTC, or any
+/// timestamp hifitime understands with an explicit time scale
+pub fn parse_utc(text: &str) -> anyhow::Result<Epoch> {
+    let text = text.trim();
+    let (date, time) = text
+        .split_once(['T', ' '])
+        .map_or((text, ""), |(date, time)| (date, time.trim()));
+    if let Ok(epoch) = Epoch::from_str(text) {
+        if time.contains(char::is_alphabetic) {
+            return Ok(epoch);
+        }
+    }
+    let time = match time.matches(':').count() {
+        _ if time.is_empty() => "00:00:00".to_string(),
+        1 => format!("{}:00", time),
+        _ => time.to_string(),
+    };
+    Epoch::from_str(&format!("{}T{} UTC", date, time))
+        .map_err(|error| anyhow::anyhow!("can't read {:?} as a UTC time: {}", text, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_utc_formats() {
+        let expected = Epoch::from_gregorian_utc(2024, 1, 31, 23, 59, 0, 0);
+        assert_eq!(parse_utc("2024-01-31 23:59").unwrap(), expected);
+        assert_eq!(parse_utc("2024-01-31T23:59:00").unwrap(), expected);
+        assert_eq!(
+            parse_utc("2024-01-31").unwrap(),
+            Epoch::from_gregorian_utc_at_midnight(2024, 1, 31)
+        );
+        assert!(parse_utc("yesterday").is_err());
+    }
+
+    #[test]
+    fn paused_clock_steps_and_jumps() {
+        let mut clock = SimClock::live();
+        clock.toggle_pause();
+        let paused_at = clock.now();
+        assert_eq!(clock.now(), paused_at);
+        clock.step(Unit::Minute * 5);
+        assert_eq!(clock.now(), paused_at + Unit::Minute * 5);
+        let target = Epoch::from_gregorian_utc_at_midnight(2024, 1, 31);
+        clock.jump(target);
+        assert_eq!(clock.now(), target);
+        assert_eq!(clock.describe(), "paused");
+        clock.change_speed(-1);
+        clock.toggle_pause();
+        assert_eq!(clock.describe(), "×-1");
+    }
+}
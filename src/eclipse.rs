@@ -0,0 +1,212 @@
+use crate::{frames::WGS84_A, propagation::Propagator, sun::sun_position};
+use hifitime::prelude::*;
+
+/// mean solar radius in km
+const SUN_RADIUS: f64 = 695700.0;
+/// step when searching for shadow transitions; LEO penumbra crossings take ~10 s,
+/// but the umbra itself lasts over half an hour, so none is skipped
+const SEARCH_STEP_SECONDS: f64 = 60.0;
+const REFINE_SECONDS: f64 = 0.5;
+
+/// How much of the sun a satellite can see
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Illumination {
+    Sunlit,
+    Penumbra,
+    Umbra,
+}
+
+impl Illumination {
+    pub fn name(self) -> &'static str {
+        match self {
+            Illumination::Sunlit => "sunlit",
+            Illumination::Penumbra => "penumbra",
+            Illumination::Umbra => "umbra",
+        }
+    }
+}
+
+/// A change of illumination, e.g. entering the penumbra
+#[derive(Clone, Debug)]
+pub struct EclipseEvent {
+    pub norad_id: u64,
+    pub name: String,
+    pub time: Epoch,
+    pub from: Illumination,
+    pub to: Illumination,
+}
+
+impl EclipseEvent {
+    /// "umbra entry", "penumbra exit", ...
+    pub fn describe(&self) -> String {
+        let deeper = |light: Illumination| match light {
+            Illumination::Sunlit => 0,
+            Illumination::Penumbra => 1,
+            Illumination::Umbra => 2,
+        };
+        if deeper(self.to) > deeper(self.from) {
+            format!("{} entry", self.to.name())
+        } else {
+            format!("{} exit", self.from.name())
+        }
+    }
+}
+
+/// Conical shadow model, comparing the apparent radii of the sun and the earth as seen
+/// from the satellite with the angle between their centers. Both positions are
+/// geocentric in km and in the same inertial frame.
+pub fn illumination(satellite: [f64; 3], sun: [f64; 3]) -> Illumination {
+    let to_sun = [
+        sun[0] - satellite[0],
+        sun[1] - satellite[1],
+        sun[2] - satellite[2],
+    ];
+    let to_earth = satellite.map(|c| -c);
+    let norm = |v: &[f64; 3]| v.iter().map(|c| c.powi(2)).sum::<f64>().sqrt();
+    let (sun_distance, earth_distance) = (norm(&to_sun), norm(&to_earth));
+
+    let sun_radius = (SUN_RADIUS / sun_distance).asin();
+    let earth_radius = (WGS84_A / earth_distance).min(1.0).asin();
+    let separation = (to_sun.iter().zip(to_earth).map(|(s, e)| s * e).sum::<f64>()
+        / (sun_distance * earth_distance))
+        .clamp(-1.0, 1.0)
+        .acos();
+
+    if separation >= sun_radius + earth_radius {
+        Illumination::Sunlit
+    } else if separation <= earth_radius - sun_radius {
+        Illumination::Umbra
+    } else {
+        Illumination::Penumbra
+    }
+}
+
+pub fn illumination_at(propagator: &Propagator, time: Epoch) -> Option<Illumination> {
+    let prediction = propagator.propagate(time).ok()?;
+    Some(illumination(prediction.position, sun_position(time)))
+}
+
+/// Every shadow transition between `start` and `end`
+pub fn eclipse_events(
+    propagator: &Propagator,
+    (start, end): (Epoch, Epoch),
+    (norad_id, name): (u64, &str),
+) -> Vec<EclipseEvent> {
+    let step = Unit::Second * SEARCH_STEP_SECONDS;
+    let mut events = Vec::new();
+    let Some(mut previous) = illumination_at(propagator, start) else {
+        return events;
+    };
+    let mut time = start;
+    while time < end {
+        let next_time = (time + step).min(end);
+        let Some(current) = illumination_at(propagator, next_time) else {
+            break;
+        };
+        if current != previous {
+            let (mut before, mut after) = (time, next_time);
+            // a penumbra crossing can be shorter than the step, so follow the first change
+            while (after - before).to_seconds() > REFINE_SECONDS {
+                let middle = before + (after - before) / 2;
+                if illumination_at(propagator, middle) == Some(previous) {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            let to = illumination_at(propagator, after).unwrap_or(current);
+            events.push(EclipseEvent {
+                norad_id,
+                name: name.to_string(),
+                time: after,
+                from: previous,
+                to,
+            });
+            previous = to;
+            time = after;
+            continue;
+        }
+        previous = current;
+        time = next_time;
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sun::AU;
+
+    #[test]
+    fn shadow_states() {
+        let sun = [AU, 0.0, 0.0];
+        // between the earth and the sun
+        assert_eq!(illumination([7000.0, 0.0, 0.0], sun), Illumination::Sunlit);
+        // directly behind the earth
+        assert_eq!(illumination([-7000.0, 0.0, 0.0], sun), Illumination::Umbra);
+        // above the pole, in full view of the sun
+        assert_eq!(illumination([0.0, 0.0, 7000.0], sun), Illumination::Sunlit);
+        // grazing the shadow edge: the penumbra is ~0.5° wide there, i.e. ~60 km at 7000 km
+        let edge = (WGS84_A.powi(2) + 10.0).sqrt();
+        assert_eq!(
+            illumination([-3000.0, edge, 0.0], sun),
+            Illumination::Penumbra
+        );
+    }
+
+    #[test]
+    fn iss_shadow_transitions() {
+        let elements = sgp4::Elements::from_tle(
+            Some("ISS".to_string()),
+            b"1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992",
+            b"2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008",
+        )
+        .unwrap();
+        let propagator = Propagator::new(&elements).unwrap();
+        let start = Epoch::from_gregorian_utc_at_midnight(2020, 7, 1);
+        let events = eclipse_events(&propagator, (start, start + Unit::Day * 1), (25544, "ISS"));
+
+        // each event starts from where the one before left off
+        for pair in events.windows(2) {
+            assert_eq!(pair[0].to, pair[1].from, "{:?}", pair);
+            assert!(pair[0].time < pair[1].time);
+        }
+        let umbra_entries: Vec<usize> = (0..events.len())
+            .filter(|&i| events[i].to == Illumination::Umbra)
+            .collect();
+        // 15.5 orbits a day, and this early in July every one of them passes the shadow
+        assert!((15..=16).contains(&umbra_entries.len()));
+        let period = Unit::Day * (1.0 / elements.mean_motion);
+        for pair in umbra_entries.windows(2) {
+            let between = events[pair[1]].time - events[pair[0]].time;
+            assert!((between - period).abs() < Unit::Minute * 1, "{}", between);
+        }
+        // through the penumbra into the umbra, and out the same way; the window may cut
+        // the first or last of them short
+        let whole: Vec<&[EclipseEvent]> = umbra_entries
+            .iter()
+            .filter_map(|&i| events.get(i.checked_sub(1)?..i + 3))
+            .collect();
+        assert!(whole.len() >= umbra_entries.len() - 1);
+        for shadow in whole {
+            let [penumbra_in, umbra_in, umbra_out, penumbra_out] = shadow else {
+                unreachable!();
+            };
+            assert_eq!(penumbra_in.describe(), "penumbra entry");
+            assert_eq!(umbra_out.describe(), "umbra exit");
+            assert_eq!(penumbra_out.describe(), "penumbra exit");
+            let penumbra = umbra_in.time - penumbra_in.time;
+            assert!(
+                penumbra > Unit::Second * 2 && penumbra < Unit::Minute * 2,
+                "{}",
+                penumbra
+            );
+            let umbra = umbra_out.time - umbra_in.time;
+            assert!(
+                umbra > Unit::Minute * 30 && umbra < Unit::Minute * 40,
+                "{}",
+                umbra
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::frames::GroundPos;
+
+/// mean earth radius in km; a sphere is plenty for drawing coverage on the map
+const EARTH_RADIUS: f64 = 6371.0;
+/// azimuth step between footprint edge points, in degrees
+const EDGE_STEP: usize = 5;
+
+/// Earth central angle (degrees) from the subsatellite point to the edge of coverage, where a
+/// user sees the satellite `min_elevation` degrees above the horizon
+pub fn earth_central_angle(alt: f64, min_elevation: f64) -> f64 {
+    let elevation = min_elevation.to_radians();
+    let nadir_limit = (EARTH_RADIUS * elevation.cos() / (EARTH_RADIUS + alt.max(0.0))).acos();
+    (nadir_limit - elevation).to_degrees().max(0.0)
+}
+
+/// (lat, lon) of the point `angle` degrees of arc from `center` toward `azimuth`
+fn destination(center: &GroundPos, angle: f64, azimuth: f64) -> (f64, f64) {
+    let (sin_lat, cos_lat) = center.lat.to_radians().sin_cos();
+    let (sin_angle, cos_angle) = angle.to_radians().sin_cos();
+    let (sin_az, cos_az) = azimuth.to_radians().sin_cos();
+    let lat = (sin_lat * cos_angle + cos_lat * sin_angle * cos_az).asin();
+    let lon = center.lon.to_radians()
+        + (sin_az * sin_angle * cos_lat).atan2(cos_angle - sin_lat * lat.sin());
+    let lon = (lon.to_degrees() + 180.0).rem_euclid(360.0) - 180.0;
+    (lat.to_degrees(), lon)
+}
+
+/// Closed outline of the coverage area around a satellite as (lat, lon) points. Over a
+/// pole the outline simply runs around it; splitting where it crosses the antimeridian is
+/// left to the projection.
+pub fn footprint(position: &GroundPos, min_elevation: f64) -> Vec<(f64, f64)> {
+    let angle = earth_central_angle(position.alt, min_elevation);
+    (0..=360)
+        .step_by(EDGE_STEP)
+        .map(|azimuth| destination(position, angle, azimuth as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arc_between(a: (f64, f64), b: (f64, f64)) -> f64 {
+        let (lat1, lat2) = (a.0.to_radians(), b.0.to_radians());
+        let dlon = (b.1 - a.1).to_radians();
+        (lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * dlon.cos())
+            .clamp(-1.0, 1.0)
+            .acos()
+            .to_degrees()
+    }
+
+    #[test]
+    fn central_angle() {
+        // from 630 km a 35° mask leaves a circle about 750 km across the ground in radius
+        let angle = earth_central_angle(630.0, 35.0);
+        assert!((angle - 6.80).abs() < 0.01, "{}", angle);
+        // down to the horizon, the limb
+        let limb = (EARTH_RADIUS / (EARTH_RADIUS + 630.0)).acos().to_degrees();
+        assert!((earth_central_angle(630.0, 0.0) - limb).abs() < 1e-9);
+    }
+
+    #[test]
+    fn outline_is_a_circle_even_over_the_pole() {
+        for center in [(0.0, 179.0), (85.0, 10.0), (-89.0, -120.0)] {
+            let position = GroundPos {
+                lat: center.0,
+                lon: center.1,
+                alt: 630.0,
+            };
+            let angle = earth_central_angle(position.alt, 35.0);
+            for point in footprint(&position, 35.0) {
+                assert!((arc_between(center, point) - angle).abs() < 1e-6);
+                assert!((-180.0..=180.0).contains(&point.1));
+            }
+        }
+    }
+}
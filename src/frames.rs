@@ -0,0 +1,374 @@
+//! Reference frame conversions for SGP4 output.
+//!
+//! SGP4 works in TEME (True Equator, Mean Equinox). Following Vallado's `teme2ecef`, TEME is
+//! rotated into the pseudo earth fixed frame (PEF) by GMST plus the kinematic terms of the
+//! equation of the equinoxes, then polar motion takes PEF to ECEF (ITRF), and finally the
+//! ECEF vector is converted to WGS-84 geodetic coordinates.
+
+use hifitime::prelude::*;
+use std::f64::consts::PI;
+
+/// WGS-84 equatorial radius in km
+pub const WGS84_A: f64 = 6378.137;
+/// WGS-84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// earth rotation rate in rad/s
+pub const EARTH_ROTATION_RATE: f64 = 7.292115146706979e-5;
+
+const ARCSEC_TO_RAD: f64 = PI / (180.0 * 3600.0);
+
+/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L431-L434
+#[derive(Clone, Copy, Debug)]
+pub struct GroundPos {
+    /// geodetic latitude in degrees
+    pub lat: f64,
+    /// longitude in degrees, -180 to 180
+    pub lon: f64,
+    /// height above the WGS-84 ellipsoid in km
+    pub alt: f64,
+}
+
+/// Earth orientation parameters, from IERS Bulletin A. Zero is good to a few hundred meters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EarthOrientation {
+    /// UT1 - UTC in seconds
+    pub ut1_utc: f64,
+    /// polar motion x in arcseconds
+    pub xp: f64,
+    /// polar motion y in arcseconds
+    pub yp: f64,
+}
+
+impl EarthOrientation {
+    /// whether nothing was configured, i.e. all parameters are zero
+    pub fn is_zero(&self) -> bool {
+        self.ut1_utc == 0.0 && self.xp == 0.0 && self.yp == 0.0
+    }
+}
+
+/// ECEF position (km) and velocity (km/s)
+pub struct EcefState {
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+}
+
+/// Greenwich mean sidereal time in radians, IAU 1982 model (Vallado `gstime`).
+/// `time` is taken as UTC and shifted to UT1 by `ut1_utc` seconds.
+pub fn calc_gmst(time: Epoch, ut1_utc: f64) -> f64 {
+    let jd_ut1 = time.to_jde_utc_days() + ut1_utc / 86400.0;
+    let tut1 = (jd_ut1 - 2451545.0) / 36525.0;
+    let seconds = -6.2e-6 * tut1.powi(3)
+        + 0.093104 * tut1.powi(2)
+        + (876600.0 * 3600.0 + 8640184.812866) * tut1
+        + 67310.54841;
+    // 240 seconds of sidereal time per degree
+    (seconds / 240.0).to_radians().rem_euclid(2.0 * PI)
+}
+
+/// Greenwich sidereal angle used to rotate TEME into PEF, in radians
+pub fn teme_sidereal_angle(time: Epoch, eop: &EarthOrientation) -> f64 {
+    let gmst = calc_gmst(time, eop.ut1_utc);
+    (gmst + kinematic_equinox_terms(time)).rem_euclid(2.0 * PI)
+}
+
+/// kinematic terms of the equation of the equinoxes in radians, applied since 1997
+fn kinematic_equinox_terms(time: Epoch) -> f64 {
+    if time.to_jde_utc_days() <= 2450449.5 {
+        return 0.0;
+    }
+    let omega = (125.04452222 - 1934.136261 * tt_centuries(time)).to_radians();
+    (0.00264 * omega.sin() + 0.000063 * (2.0 * omega).sin()) * ARCSEC_TO_RAD
+}
+
+/// Julian centuries of TT since J2000
+fn tt_centuries(time: Epoch) -> f64 {
+    (time.to_jde_tt_days() - 2451545.0) / 36525.0
+}
+
+/// rotates a TEME vector into PEF
+fn teme_to_pef(r: [f64; 3], theta: f64) -> [f64; 3] {
+    let (sin, cos) = theta.sin_cos();
+    [cos * r[0] + sin * r[1], -sin * r[0] + cos * r[1], r[2]]
+}
+
+/// The largest terms of the IAU 1980 nutation series: multipliers of l, l', F, D and Ω, then
+/// longitude and obliquity coefficients (and their rates per century) in 0.0001". The
+/// terms left out add up to a few milliarcseconds, well under SGP4's own error.
+const NUTATION_TERMS: [([f64; 5], f64, f64, f64, f64); 15] = [
+    ([0.0, 0.0, 0.0, 0.0, 1.0], -171996.0, -174.2, 92025.0, 8.9),
+    ([0.0, 0.0, 2.0, -2.0, 2.0], -13187.0, -1.6, 5736.0, -3.1),
+    ([0.0, 0.0, 2.0, 0.0, 2.0], -2274.0, -0.2, 977.0, -0.5),
+    ([0.0, 0.0, 0.0, 0.0, 2.0], 2062.0, 0.2, -895.0, 0.5),
+    ([0.0, 1.0, 0.0, 0.0, 0.0], 1426.0, -3.4, 54.0, -0.1),
+    ([1.0, 0.0, 0.0, 0.0, 0.0], 712.0, 0.1, -7.0, 0.0),
+    ([0.0, 1.0, 2.0, -2.0, 2.0], -517.0, 1.2, 224.0, -0.6),
+    ([0.0, 0.0, 2.0, 0.0, 1.0], -386.0, -0.4, 200.0, 0.0),
+    ([1.0, 0.0, 2.0, 0.0, 2.0], -301.0, 0.0, 129.0, -0.1),
+    ([0.0, -1.0, 2.0, -2.0, 2.0], 217.0, -0.5, -95.0, 0.3),
+    ([1.0, 0.0, 0.0, -2.0, 0.0], -158.0, 0.0, -1.0, 0.0),
+    ([0.0, 0.0, 2.0, -2.0, 1.0], 129.0, 0.1, -70.0, 0.0),
+    ([-1.0, 0.0, 2.0, 0.0, 2.0], 123.0, 0.0, -53.0, 0.0),
+    ([1.0, 0.0, 0.0, 0.0, 1.0], 63.0, 0.1, -33.0, 0.0),
+    ([0.0, 0.0, 0.0, 2.0, 0.0], 63.0, 0.0, -2.0, 0.0),
+];
+
+/// nutation in longitude and obliquity and the mean obliquity of the ecliptic, in radians
+/// (Vallado's `nutation`, IAU 1980)
+fn nutation(ttt: f64) -> (f64, f64, f64) {
+    // Delaunay arguments in degrees
+    let polynomial = |c0: f64, c1: f64, c2: f64, c3: f64| {
+        (c0 + (((c3 * ttt + c2) * ttt + c1) * ttt) / 3600.0).to_radians()
+    };
+    let arguments = [
+        polynomial(134.96298139, 1717915922.6330, 31.310, 0.064),
+        polynomial(357.52772333, 129596581.2240, -0.577, -0.012),
+        polynomial(93.27191028, 1739527263.1370, -13.257, 0.011),
+        polynomial(297.85036306, 1602961601.3280, -6.891, 0.019),
+        polynomial(125.04452222, -6962890.5390, 7.455, 0.008),
+    ];
+    let (mut dpsi, mut deps) = (0.0, 0.0);
+    for (multipliers, psi, psi_rate, eps, eps_rate) in NUTATION_TERMS {
+        let angle: f64 = multipliers.iter().zip(arguments).map(|(m, a)| m * a).sum();
+        dpsi += (psi + psi_rate * ttt) * angle.sin();
+        deps += (eps + eps_rate * ttt) * angle.cos();
+    }
+    let mean_eps = 84381.448 + ((0.001813 * ttt - 0.00059) * ttt - 46.8150) * ttt;
+    (
+        dpsi * 1e-4 * ARCSEC_TO_RAD,
+        deps * 1e-4 * ARCSEC_TO_RAD,
+        mean_eps * ARCSEC_TO_RAD,
+    )
+}
+
+/// rotates the coordinate frame of `r` by `angle` about `axis` (0 is x), Vallado's `rot1..3`
+fn rotate(r: [f64; 3], axis: usize, angle: f64) -> [f64; 3] {
+    let (sin, cos) = angle.sin_cos();
+    let (i, j) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut rotated = r;
+    rotated[i] = cos * r[i] + sin * r[j];
+    rotated[j] = -sin * r[i] + cos * r[j];
+    rotated
+}
+
+/// rotates a TEME vector into the mean equator and equinox of J2000 (EME2000) through the
+/// true and mean of date frames (Vallado's `teme2eci`, IAU 1976 precession)
+pub fn teme_to_j2000(r: [f64; 3], time: Epoch) -> [f64; 3] {
+    let ttt = tt_centuries(time);
+    let (dpsi, deps, mean_eps) = nutation(ttt);
+    let equinox = dpsi * mean_eps.cos() + kinematic_equinox_terms(time);
+    let true_of_date = rotate(r, 2, -equinox);
+    let mean_of_date = rotate(
+        rotate(rotate(true_of_date, 0, mean_eps + deps), 2, dpsi),
+        0,
+        -mean_eps,
+    );
+    let arcsec = |c1: f64, c2: f64, c3: f64| ((c3 * ttt + c2) * ttt + c1) * ttt * ARCSEC_TO_RAD;
+    let zeta = arcsec(2306.2181, 0.30188, 0.017998);
+    let theta = arcsec(2004.3109, -0.42665, -0.041833);
+    let z = arcsec(2306.2181, 1.09468, 0.018203);
+    rotate(rotate(rotate(mean_of_date, 2, z), 1, -theta), 2, zeta)
+}
+
+/// applies polar motion to take a PEF vector to ECEF (transpose of Vallado's `polarm`)
+fn pef_to_ecef(r: [f64; 3], eop: &EarthOrientation) -> [f64; 3] {
+    let (sin_xp, cos_xp) = (eop.xp * ARCSEC_TO_RAD).sin_cos();
+    let (sin_yp, cos_yp) = (eop.yp * ARCSEC_TO_RAD).sin_cos();
+    [
+        cos_xp * r[0] + sin_xp * sin_yp * r[1] + sin_xp * cos_yp * r[2],
+        cos_yp * r[1] - sin_yp * r[2],
+        -sin_xp * r[0] + cos_xp * sin_yp * r[1] + cos_xp * cos_yp * r[2],
+    ]
+}
+
+pub fn teme_to_ecef(position: [f64; 3], time: Epoch, eop: &EarthOrientation) -> [f64; 3] {
+    pef_to_ecef(teme_to_pef(position, teme_sidereal_angle(time, eop)), eop)
+}
+
+/// converts a TEME state vector, removing the earth's rotation from the velocity
+pub fn teme_to_ecef_state(
+    position: [f64; 3],
+    velocity: [f64; 3],
+    time: Epoch,
+    eop: &EarthOrientation,
+) -> EcefState {
+    let theta = teme_sidereal_angle(time, eop);
+    let r_pef = teme_to_pef(position, theta);
+    let v_pef = teme_to_pef(velocity, theta);
+    let v_pef = [
+        v_pef[0] + EARTH_ROTATION_RATE * r_pef[1],
+        v_pef[1] - EARTH_ROTATION_RATE * r_pef[0],
+        v_pef[2],
+    ];
+    EcefState {
+        position: pef_to_ecef(r_pef, eop),
+        velocity: pef_to_ecef(v_pef, eop),
+    }
+}
+
+/// ECEF position in km to WGS-84 geodetic coordinates
+pub fn ecef_to_geodetic(r: [f64; 3]) -> GroundPos {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let p = r[0].hypot(r[1]);
+    let lon = r[1].atan2(r[0]);
+    let mut lat = r[2].atan2(p * (1.0 - e2));
+    let mut alt = 0.0;
+    for _ in 0..10 {
+        let sin_lat = lat.sin();
+        let n = WGS84_A / (1.0 - e2 * sin_lat.powi(2)).sqrt();
+        // this form of the height stays well conditioned near the poles
+        alt = p * lat.cos() + (r[2] + e2 * n * sin_lat) * sin_lat - n;
+        let next = r[2].atan2(p * (1.0 - e2 * n / (n + alt)));
+        if (next - lat).abs() < 1e-12 {
+            lat = next;
+            break;
+        }
+        lat = next;
+    }
+    GroundPos {
+        lat: lat.to_degrees(),
+        lon: lon.to_degrees(),
+        alt,
+    }
+}
+
+/// WGS-84 geodetic coordinates to an ECEF position in km
+pub fn geodetic_to_ecef(pos: &GroundPos) -> [f64; 3] {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let (sin_lat, cos_lat) = pos.lat.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = pos.lon.to_radians().sin_cos();
+    let n = WGS84_A / (1.0 - e2 * sin_lat.powi(2)).sqrt();
+    [
+        (n + pos.alt) * cos_lat * cos_lon,
+        (n + pos.alt) * cos_lat * sin_lon,
+        (n * (1.0 - e2) + pos.alt) * sin_lat,
+    ]
+}
+
+/// TEME position in km straight to WGS-84 geodetic coordinates
+pub fn teme_to_geodetic(position: [f64; 3], time: Epoch, eop: &EarthOrientation) -> GroundPos {
+    ecef_to_geodetic(teme_to_ecef(position, time, eop))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::propagation::Propagator;
+    use core::str::FromStr;
+
+    fn utc(text: &str) -> Epoch {
+        Epoch::from_str(&format!("{} UTC", text)).unwrap()
+    }
+
+    fn assert_close(actual: [f64; 3], expected: [f64; 3], tolerance: f64) {
+        let error = actual
+            .iter()
+            .zip(expected)
+            .map(|(a, e)| (a - e).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        assert!(
+            error < tolerance,
+            "{:?} is {} away from {:?}",
+            actual,
+            error,
+            expected
+        );
+    }
+
+    #[test]
+    fn gmst_at_j2000() {
+        // 18h 41m 50.54841s
+        let gmst = calc_gmst(utc("2000-01-01T12:00:00"), 0.0).to_degrees();
+        assert!((gmst - 280.46061837504).abs() < 1e-8, "{}", gmst);
+    }
+
+    #[test]
+    fn gmst_vallado_example_3_5() {
+        // Vallado, Fundamentals of Astrodynamics and Applications, example 3-5
+        let gmst = calc_gmst(utc("1992-08-20T12:14:00"), 0.0).to_degrees();
+        assert!((gmst - 152.578787886).abs() < 1e-6, "{}", gmst);
+    }
+
+    #[test]
+    fn gmst_applies_ut1_offset() {
+        let time = utc("2004-04-06T07:51:28.386009");
+        let shifted = calc_gmst(time, 0.5) - calc_gmst(time, 0.0);
+        // one UT1 second is 1.0027379 sidereal seconds
+        let expected = (0.5 * 1.00273790935 / 240.0_f64).to_radians();
+        assert!((shifted - expected).abs() < 1e-8, "{}", shifted);
+    }
+
+    #[test]
+    fn teme_to_ecef_vallado_example() {
+        // Vallado et al., "Revisiting Spacetrack Report #3", teme2ecef test vector
+        let eop = EarthOrientation {
+            ut1_utc: -0.4399619,
+            xp: -0.140682,
+            yp: 0.333309,
+        };
+        let ecef = teme_to_ecef(
+            [5094.18016210, 6127.64465950, 6380.34453270],
+            utc("2004-04-06T07:51:28.386009"),
+            &eop,
+        );
+        assert_close(ecef, [-1033.4793830, 7901.2952754, 6380.3565958], 1e-3);
+    }
+
+    #[test]
+    fn teme_to_j2000_vallado_example() {
+        // Vallado et al., "Revisiting Spacetrack Report #3", teme2eci test vector
+        let time = utc("2004-04-06T07:51:28.386009");
+        let position = teme_to_j2000([5094.18016210, 6127.64465950, 6380.34453270], time);
+        assert_close(
+            position,
+            [5102.50895790, 6123.01140070, 6378.13692820],
+            5e-3,
+        );
+        let velocity = teme_to_j2000([-4.746131487, 0.785818041, 5.531931288], time);
+        assert_close(velocity, [-4.743220157, 0.790536497, 5.533755727], 1e-5);
+    }
+
+    #[test]
+    fn ecef_to_geodetic_vallado_example_3_3() {
+        let pos = ecef_to_geodetic([6524.834, 6862.875, 6448.296]);
+        assert!((pos.lat - 34.352496).abs() < 1e-5, "{:?}", pos);
+        assert!((pos.lon - 46.4464).abs() < 1e-4, "{:?}", pos);
+        assert!((pos.alt - 5085.22).abs() < 1e-2, "{:?}", pos);
+    }
+
+    #[test]
+    fn ecef_to_geodetic_at_pole() {
+        let pos = ecef_to_geodetic([0.0, 0.0, 6356.752314245 + 500.0]);
+        assert!((pos.lat - 90.0).abs() < 1e-9, "{:?}", pos);
+        assert!((pos.alt - 500.0).abs() < 1e-6, "{:?}", pos);
+    }
+
+    #[test]
+    fn sub_satellite_point_case_00005() {
+        // from the TLE through SGP4, the GMST rotation and the geodetic conversion. The
+        // expected points are the TEME positions published in tcppver.out, Vallado's SGP4
+        // verification set, rotated by IAU-82 GMST and solved on WGS-84 separately. Those
+        // positions use WGS-72 while we propagate with WGS-84, a few tens of meters apart.
+        let elements = sgp4::Elements::from_tle(
+            None,
+            b"1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+            b"2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+        )
+        .unwrap();
+        let propagator = Propagator::new(&elements).unwrap();
+        let cases = [
+            (0.0, 0.00032, 149.95574, 782.537),
+            (360.0, -23.70535, -81.14468, 2456.906),
+            (720.0, 18.69927, 118.26429, 3831.631),
+        ];
+        for (minutes, lat, lon, alt) in cases {
+            let time = propagator.epoch + Unit::Minute * minutes;
+            let prediction = propagator.propagate(time).unwrap();
+            let pos = teme_to_geodetic(prediction.position, time, &EarthOrientation::default());
+            assert!((pos.lat - lat).abs() < 1e-3, "{} {:?}", minutes, pos);
+            assert!((pos.lon - lon).abs() < 1e-3, "{} {:?}", minutes, pos);
+            assert!((pos.alt - alt).abs() < 0.1, "{} {:?}", minutes, pos);
+        }
+    }
+}
@@ -1,233 +1,292 @@
-use core::str::FromStr;
+mod app;
+mod cache;
+mod cli;
+mod clock;
+mod eclipse;
+mod footprint;
+mod frames;
+mod observer;
+mod oem;
+mod passes;
+mod positions;
+mod predictions;
+mod projection;
+mod propagation;
+mod refresh;
+mod satellite;
+mod selection;
+mod source;
+mod sun;
+mod tracks;
+mod ui;
+mod viewport;
+mod visibility;
+mod world;
+mod xml;
+
+use anyhow::Context;
+use app::App;
+use cache::{Cache, Freshness};
+use cli::{Args, USAGE};
 use crossterm::{
-    event::{self, KeyCode, KeyEventKind},
+    event::{self, DisableMouseCapture, EnableMouseCapture, KeyEventKind},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use hifitime::prelude::*;
+use ratatui::prelude::{CrosstermBackend, Terminal};
+use refresh::Refresher;
+use source::{ElementSource, Loaded};
+use std::{io::stdout, path::Path};
+use tracks::TrackFormat;
 
-use ratatui::{
-    prelude::{CrosstermBackend, Terminal},
-    style::{Color, Stylize},
-    widgets::{
-        canvas::{Canvas, Map, MapResolution},
-        Block, Borders,
-    },
-};
-use sgp4::{Elements, Prediction};
-use std::{f64::consts::PI, io::stdout};
-
-/// Based on https://github.com/colej4/satapp/blob/main/src-tauri/src/tracking.rs#L419-L423
-struct SphericalPoint {
-    rho: f64,
-    theta: f64,
-    phi: f64,
+/// loads elements without a UI, falling back to the cache if the source can't be reached
+fn load_blocking(app: &mut App, cache: Option<&Cache>) -> anyhow::Result<()> {
+    match app.args.source.load() {
+        Ok(Loaded {
+            elements: elements_vec,
+            skipped,
+        }) => {
+            for error in skipped {
+                eprintln!("warning: skipped {}", error);
+            }
+            let now = Epoch::now().unwrap();
+            if let Some(cache) = cache {
+                let _ = cache.store(&elements_vec, now);
+            }
+            app.load(elements_vec, now);
+        }
+        Err(error) => {
+            let cached = cache
+                .and_then(|cache| cache.load().ok().flatten())
+                .ok_or(error)?;
+            eprintln!(
+                "warning: using cached elements from {}",
+                clock::format_utc(cached.fetched_at)
+            );
+            app.load(cached.elements, cached.fetched_at);
+        }
+    }
+    if app.satellites.is_empty() {
+        anyhow::bail!("{}", app.status);
+    }
+    Ok(())
 }
 
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L425-L429
-pub struct RectangularPoint {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+/// `--passes`: prints the upcoming passes over the observer and exits
+fn print_passes(app: &mut App, cache: Option<&Cache>) -> anyhow::Result<()> {
+    let Some(observer) = app.observer else {
+        anyhow::bail!("--passes needs an --observer");
+    };
+    load_blocking(app, cache)?;
+    let now = Epoch::now().unwrap();
+    app.passes = predictions::passes(
+        &app.satellites,
+        &observer,
+        &app.args.earth_orientation,
+        (now, now + Unit::Day * app.args.pass_days),
+        app.args.min_elevation,
+        &app.args.standard_magnitudes,
+    );
+    passes::sort_passes(&mut app.passes, app.pass_sort, app.pass_reverse);
+    println!(
+        "{:<16} {:>6}  {:<19}  {:>5}  {:<8}  {:>5}  {:<8}  {:>5}  {:>7}  {:>4}",
+        "satellite", "norad", "AOS (UTC)", "az", "TCA", "el", "LOS", "az", "length", "mag"
+    );
+    for pass in app.upcoming_passes(now, &app.shown_ids()) {
+        println!(
+            "{:<16} {:>6}  {:<19}  {:>5.1}  {:<8}  {:>5.1}  {:<8}  {:>5.1}  {:>7}  {:>4}",
+            pass.name,
+            pass.norad_id,
+            clock::format_utc(pass.aos),
+            pass.aos_azimuth,
+            clock::format_utc_time(pass.tca),
+            pass.max_elevation,
+            clock::format_utc_time(pass.los),
+            pass.los_azimuth,
+            clock::format_span(pass.duration()),
+            pass.magnitude
+                .map(|magnitude| format!("{:.1}", magnitude))
+                .unwrap_or_default(),
+        );
+    }
+    Ok(())
 }
 
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L431-L434
-pub struct GroundPos {
-    pub lat: f64,
-    pub lon: f64,
+/// `--positions`: prints where the satellites are at `--at` or now and exits
+fn print_positions(app: &mut App, cache: Option<&Cache>) -> anyhow::Result<()> {
+    load_blocking(app, cache)?;
+    let time = app.args.at.unwrap_or_else(|| Epoch::now().unwrap());
+    let positions = positions::positions(app.satellites.iter(), time, &app.args.earth_orientation);
+    print!("{}", positions::render(&positions, app.args.format)?);
+    Ok(())
 }
 
-/// takes in a point in rectangular coordinates, returns spherical coordinates
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L11-L21
-fn rect_to_spherical(r: &RectangularPoint) -> SphericalPoint {
-    let rho = f64::sqrt(r.x.powi(2) + r.y.powi(2) + r.z.powi(2));
-    let theta = f64::atan2(r.y, r.x);
-    let phi = f64::atan2(f64::sqrt(r.x.powf(2.0) + r.y.powf(2.0)), r.z);
-    return SphericalPoint {
-        rho: rho,
-        theta: theta,
-        phi: phi,
+/// `--oem`: writes an ephemeris of every satellite over the span and exits
+fn export_oem(app: &mut App, cache: Option<&Cache>, path: &Path) -> anyhow::Result<()> {
+    if app.args.frame == oem::Frame::Itrf && app.args.earth_orientation.is_zero() {
+        eprintln!(
+            "warning: without --ut1-utc and --polar-motion, ITRF states can be off by a few \
+             hundred meters"
+        );
+    }
+    load_blocking(app, cache)?;
+    let start = app.args.at.unwrap_or_else(|| Epoch::now().unwrap());
+    let span = (
+        start,
+        start + Unit::Hour * app.args.span,
+        Unit::Second * app.args.step,
+    );
+    let mut ephemerides = Vec::new();
+    for sat in app.satellites.iter() {
+        match oem::ephemeris(sat, span, app.args.frame, &app.args.earth_orientation) {
+            Ok(ephemeris) => ephemerides.push(ephemeris),
+            Err(error) => eprintln!("warning: {}: {}", sat.elements.norad_id, error),
+        }
+    }
+    if ephemerides.is_empty() {
+        anyhow::bail!("no satellite could be propagated");
+    }
+    let created = Epoch::now().unwrap();
+    let text = if path.extension().is_some_and(|extension| extension == "xml") {
+        oem::xml(&ephemerides, app.args.frame, created)
+    } else {
+        oem::kvn(&ephemerides, app.args.frame, created)
     };
+    std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))?;
+    eprintln!(
+        "wrote {} ephemerides to {}",
+        ephemerides.len(),
+        path.display()
+    );
+    Ok(())
 }
 
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L30-L42
-fn spherical_to_lat_lon(s: &SphericalPoint, time: Epoch) -> GroundPos {
-    let lat = ((s.phi * 180.0 / PI) - 90.0) * -1.0;
-    let sidereal_time = calc_gmst(time) as f64 / 86400.0 * 360.0;
-    let mut lon = ((s.theta * 180.0 / PI) - sidereal_time) % 360.0;
-    if lon < -180.0 {
-        lon += 360.0;
+/// `--tracks`: writes the ground tracks the map would show and exits
+fn export_tracks(
+    app: &mut App,
+    cache: Option<&Cache>,
+    path: &Path,
+    format: TrackFormat,
+) -> anyhow::Result<()> {
+    load_blocking(app, cache)?;
+    let time = app.args.at.unwrap_or_else(|| Epoch::now().unwrap());
+    app.update_tracks(time);
+    let snapshot = app.snapshot(time);
+    for (sat, error) in &snapshot.lost {
+        eprintln!("warning: {}: {}", sat.elements.norad_id, error);
     }
-    if lon > 180.0 {
-        lon -= 360.0;
+    if snapshot.tracked.is_empty() {
+        anyhow::bail!("no satellite could be propagated");
     }
-
-    return GroundPos { lat: lat, lon: lon };
-}
-
-/// returns current gmst in seconds
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L44-L53
-pub fn calc_gmst(time: Epoch) -> f64 {
-    let now = time;
-    let s = (now.to_et_seconds() % 86400.0) - 43269.1839244;
-    let t = (now.to_jde_et_days() - s / 86400.0 - 2451545.0) / 36525.0; //days since january 1, 4713 BC noon
-    let h0 = 24110.54841 + 8640184.812866 * t + 0.093104 * t.powi(2); //the sidereal time at midnight this morning
-    let h1 = 1.00273790935 + 5.9 * 10.0f64.powf(-11.0) * t;
-    let rot = (h0 + h1 * s) % 86400.0;
-    return rot;
+    let text = match format {
+        TrackFormat::Kml => tracks::kml(&snapshot.tracked),
+        TrackFormat::GeoJson => tracks::geojson(&snapshot.tracked)?,
+    };
+    std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))?;
+    eprintln!(
+        "wrote {} ground tracks to {}",
+        snapshot.tracked.len(),
+        path.display()
+    );
+    Ok(())
 }
 
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L60-L77
-fn get_prediction(time: Epoch, elements: &Elements) -> Option<Prediction> {
-    let epoch = Epoch::from_str(format!("{} UTC", elements.datetime).as_str()).unwrap();
-    let duration = time - epoch;
-    let constants = sgp4::Constants::from_elements(&elements).unwrap();
-    //println!("last epoch was at {}", epoch);
-    //println!("last epoch was {} ago", duration);
-    let prediction =
-        constants.propagate(sgp4::MinutesSinceEpoch(duration.to_seconds() / 60 as f64));
-    match prediction {
-        Ok(pred) => return Some(pred),
-        Err(_) => {
-            //println!("{:?} at sat {}", e, elements.norad_id);
-            return None;
-        }
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse()?;
+    if args.help {
+        print!("{}", USAGE);
+        return Ok(());
     }
 
-    //println!("        r = {:?} km", prediction.position);
-    //println!("        ṙ = {:?} km.s⁻¹", prediction.velocity);
-}
-
-/// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L79-L94
-pub fn get_sat_lat_lon(time: Epoch, elements: &Elements) -> Option<GroundPos> {
-    let pred_option = get_prediction(time, elements);
-    if let Some(prediction) = pred_option {
-        let x = prediction.position[0];
-        let y = prediction.position[1];
-        let z = prediction.position[2];
-        let rect = RectangularPoint { x: x, y: y, z: z };
-        let spher = rect_to_spherical(&rect);
-        let g = spherical_to_lat_lon(&spher, time);
-        //println!("sat is at ({}, {}) at {:?}", g.lat, g.lon, time);
-        return Some(g);
+    let cache = if args.no_cache {
+        None
     } else {
-        return None;
+        args.cache_dir
+            .clone()
+            .or_else(Cache::default_dir)
+            .zip(args.source.cache_key())
+            .map(|(dir, key)| Cache::new(dir, key))
+    };
+
+    let mut app = App::new(args);
+    if app.args.print_passes {
+        return print_passes(&mut app, cache.as_ref());
     }
-}
+    if app.args.print_positions {
+        return print_positions(&mut app, cache.as_ref());
+    }
+    if let Some(path) = app.args.oem_path.clone() {
+        return export_oem(&mut app, cache.as_ref(), &path);
+    }
+    if let Some((path, format)) = app.args.tracks_path.clone() {
+        return export_tracks(&mut app, cache.as_ref(), &path, format);
+    }
+    let already_loaded = match app.args.source {
+        ElementSource::Celestrak(_) => {
+            app.freshness = Freshness::Fetching(app.args.source.describe());
+            // a missing or unreadable cache just means we wait for the network
+            if let Some(cached) = cache.as_ref().and_then(|cache| cache.load().ok().flatten()) {
+                app.load(cached.elements, cached.fetched_at);
+                app.freshness = Freshness::Cached {
+                    fetched_at: cached.fetched_at,
+                    refresh_failed: false,
+                };
+            }
+            false
+        }
+        _ => {
+            let loaded = app.args.source.load()?;
+            app.status = format!(
+                "loaded {} element sets from {}{}",
+                loaded.elements.len(),
+                app.args.source.describe(),
+                loaded.skipped_note()
+            );
+            app.load(loaded.elements, Epoch::now().unwrap());
+            true
+        }
+    };
+    let refresher = Refresher::spawn(
+        app.args.source.clone(),
+        cache.clone(),
+        app.args.refresh_interval,
+        already_loaded,
+    );
 
-fn main() -> anyhow::Result<()> {
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
     enable_raw_mode()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
     terminal.clear()?;
 
-    terminal
-        .draw(|frame| {
-            let area = frame.size();
-            frame.render_widget(
-                Canvas::default()
-                    .block(
-                        Block::default()
-                            .title("Fetching orbits from celestrak...")
-                            .borders(Borders::ALL),
-                    )
-                    .x_bounds([-180.0, 180.0])
-                    .y_bounds([-90.0, 90.0])
-                    .paint(|ctx| {
-                        ctx.draw(&Map {
-                            resolution: MapResolution::High,
-                            color: Color::White,
-                        });
-                        ctx.layer();
-                    }),
-                area,
-            )
-        })
-        .unwrap();
-
-    let response = ureq::get("https://celestrak.com/NORAD/elements/gp.php")
-        // .query("GROUP", "active") // We can query for all active satellites & then filter to just Kuiper (maybe in the future celestrak will offer a kuiper group)
-        .query("INTDES", "2023-154") // Or we can query for just the protosat launch
-        .query("FORMAT", "json")
-        .call()?;
-    let elements_vec: Vec<sgp4::Elements> = response.into_json()?;
-    let kuiper_sats = elements_vec
-        .iter()
-        .filter(|entry| {
-            entry
-                .object_name
-                .as_ref()
-                .is_some_and(|name| name.starts_with("KUIPER"))
-        })
-        .collect::<Vec<&Elements>>();
     loop {
-        let current_time = Epoch::now().unwrap();
-        let next_orbit_end = current_time + (Unit::Minute * 94.5);
-        let predictions = TimeSeries::exclusive(current_time, next_orbit_end, Unit::Minute * 2.5);
+        while let Some(event) = refresher.poll() {
+            app.apply(event, refresher.interval());
+        }
 
-        let sat_pos: Vec<(&&Elements, Vec<GroundPos>)> = kuiper_sats
-            .iter()
-            .map(|sat| {
-                (
-                    sat,
-                    predictions
-                        .clone()
-                        .map(|time| get_sat_lat_lon(time, sat).unwrap())
-                        .collect(),
-                )
-            })
-            .collect();
-        terminal.draw(|frame| {
-            let area = frame.size();
-            frame.render_widget(
-                Canvas::default()
-                    .block(
-                        Block::default()
-                            .title(current_time.to_string())
-                            .borders(Borders::ALL),
-                    )
-                    .x_bounds([-180.0, 180.0])
-                    .y_bounds([-90.0, 90.0])
-                    .paint(|ctx| {
-                        ctx.draw(&Map {
-                            resolution: MapResolution::High,
-                            color: Color::White,
-                        });
-                        ctx.layer();
-                        sat_pos.iter().for_each(|(sat, pos)| {
-                            pos.iter().for_each(|prediction| {
-                                ctx.print(prediction.lon, prediction.lat, ".".red())
-                            });
-                            ctx.print(
-                                pos[0].lon,
-                                pos[0].lat,
-                                format!(
-                                    "🛰️{}",
-                                    sat.object_name
-                                        .as_ref()
-                                        .unwrap()
-                                        .strip_prefix("KUIPER-P")
-                                        .unwrap()
-                                ),
-                            );
-                            ctx.layer();
-                        });
-                    }),
-                area,
-            );
-        })?;
+        let current_time = app.clock.now();
+        app.update_predictions(current_time);
+        app.update_view(current_time);
+        app.update_tracks(current_time);
+        let snapshot = app.snapshot(current_time);
+        let mut map_area = app.map_area;
+        terminal.draw(|frame| map_area = ui::draw(frame, &app, &snapshot))?;
+        app.map_area = map_area;
 
         if event::poll(std::time::Duration::from_millis(16))? {
-            if let event::Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q')
-                    || key.code == KeyCode::Char('Q')
-                {
-                    break;
-                }
+            match event::read()? {
+                event::Event::Key(key) if key.kind == KeyEventKind::Press => app.on_key(key.code),
+                event::Event::Mouse(mouse) => app.on_mouse(mouse),
+                _ => {}
             }
         }
+        if app.quit {
+            break;
+        }
     }
 
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
@@ -0,0 +1,116 @@
+use crate::frames::{geodetic_to_ecef, EcefState, GroundPos};
+
+/// A ground station or observer on the WGS-84 ellipsoid
+#[derive(Clone, Copy, Debug)]
+pub struct Observer {
+    pub location: GroundPos,
+    /// cached ECEF position in km
+    ecef: [f64; 3],
+}
+
+/// Where a satellite appears in the observer's sky
+#[derive(Clone, Copy, Debug)]
+pub struct LookAngles {
+    /// degrees clockwise from north
+    pub azimuth: f64,
+    /// degrees above the horizon
+    pub elevation: f64,
+    /// km
+    pub range: f64,
+    /// km/s, positive when the satellite is moving away
+    pub range_rate: f64,
+}
+
+impl Observer {
+    pub fn new(location: GroundPos) -> Self {
+        Observer {
+            location,
+            ecef: geodetic_to_ecef(&location),
+        }
+    }
+
+    /// ECEF position in km
+    pub fn ecef(&self) -> [f64; 3] {
+        self.ecef
+    }
+
+    /// rotates an ECEF vector into the local east, north, up frame
+    pub fn enu(&self, v: [f64; 3]) -> [f64; 3] {
+        let (sin_lat, cos_lat) = self.location.lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.location.lon.to_radians().sin_cos();
+        [
+            -sin_lon * v[0] + cos_lon * v[1],
+            -sin_lat * cos_lon * v[0] - sin_lat * sin_lon * v[1] + cos_lat * v[2],
+            cos_lat * cos_lon * v[0] + cos_lat * sin_lon * v[1] + sin_lat * v[2],
+        ]
+    }
+
+    /// topocentric azimuth, elevation, range and range rate of an ECEF satellite state
+    pub fn look_angles(&self, sat: &EcefState) -> LookAngles {
+        let rho = [
+            sat.position[0] - self.ecef[0],
+            sat.position[1] - self.ecef[1],
+            sat.position[2] - self.ecef[2],
+        ];
+        let range = rho.iter().map(|c| c.powi(2)).sum::<f64>().sqrt();
+        let [east, north, up] = self.enu(rho);
+        // the observer is fixed in ECEF, so only the satellite's velocity contributes
+        let range_rate = rho
+            .iter()
+            .zip(sat.velocity)
+            .map(|(r, v)| r * v)
+            .sum::<f64>()
+            / range;
+        LookAngles {
+            azimuth: east.atan2(north).to_degrees().rem_euclid(360.0),
+            elevation: (up / range).asin().to_degrees(),
+            range,
+            range_rate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(position: [f64; 3], velocity: [f64; 3]) -> EcefState {
+        EcefState { position, velocity }
+    }
+
+    #[test]
+    fn satellite_overhead() {
+        let observer = Observer::new(GroundPos {
+            lat: 45.0,
+            lon: -120.0,
+            alt: 0.0,
+        });
+        let above = geodetic_to_ecef(&GroundPos {
+            lat: 45.0,
+            lon: -120.0,
+            alt: 600.0,
+        });
+        let look = observer.look_angles(&state(above, [0.0; 3]));
+        assert!((look.elevation - 90.0).abs() < 1e-6, "{:?}", look);
+        assert!((look.range - 600.0).abs() < 1e-6, "{:?}", look);
+    }
+
+    #[test]
+    fn azimuth_and_range_rate() {
+        let observer = Observer::new(GroundPos {
+            lat: 0.0,
+            lon: 0.0,
+            alt: 0.0,
+        });
+        // due east along the equator, moving further east
+        let east = geodetic_to_ecef(&GroundPos {
+            lat: 0.0,
+            lon: 5.0,
+            alt: 500.0,
+        });
+        let look = observer.look_angles(&state(east, [0.0, 7.0, 0.0]));
+        assert!((look.azimuth - 90.0).abs() < 1e-6, "{:?}", look);
+        assert!(look.elevation > 0.0 && look.elevation < 90.0, "{:?}", look);
+        assert!(look.range_rate > 0.0, "{:?}", look);
+    }
+}
@@ -0,0 +1,328 @@
+//! `--oem`: state vectors over a span as a CCSDS Orbit Ephemeris Message (CCSDS 502.0-B-3),
+//! in the keyword = value (KVN) or the XML form, one segment per satellite
+
+use crate::{
+    frames::{teme_to_ecef_state, teme_to_j2000, EarthOrientation},
+    propagation::PropagationError,
+    satellite::Satellite,
+    selection::full_name,
+    xml,
+};
+use hifitime::prelude::*;
+
+const ORIGINATOR: &str = "tuiper";
+
+/// Reference frame the state vectors are given in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frame {
+    /// SGP4's own true equator, mean equinox frame
+    Teme,
+    /// earth fixed, by GMST and the configured earth orientation, which approximates the
+    /// ITRF realization the Bulletin A values refer to. The message names ITRF2014; how
+    /// close it gets depends on the earth orientation given, written out as a comment.
+    Itrf,
+    /// mean equator and equinox of J2000
+    Eme2000,
+}
+
+impl Frame {
+    /// "teme", "itrf" or "eme2000"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "teme" => Some(Frame::Teme),
+            "itrf" => Some(Frame::Itrf),
+            "eme2000" | "j2000" => Some(Frame::Eme2000),
+            _ => None,
+        }
+    }
+
+    /// REF_FRAME value
+    fn ccsds_name(self) -> &'static str {
+        match self {
+            Frame::Teme => "TEME",
+            Frame::Itrf => "ITRF2014",
+            Frame::Eme2000 => "EME2000",
+        }
+    }
+}
+
+/// Position (km) and velocity (km/s) at one time
+#[derive(Clone, Copy, Debug)]
+pub struct State {
+    pub time: Epoch,
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+}
+
+/// One satellite's states, an OEM segment
+pub struct Ephemeris {
+    pub name: String,
+    pub object_id: String,
+    /// COMMENT lines at the start of the data
+    pub comments: Vec<String>,
+    pub states: Vec<State>,
+}
+
+/// states of `sat` every `step` from `start` to `stop`, converted to `frame`. An orbit that
+/// stops propagating part way (e.g. decaying) ends there; one that can't be propagated at
+/// `start` is an error.
+pub fn ephemeris(
+    sat: &Satellite,
+    (start, stop, step): (Epoch, Epoch, Duration),
+    frame: Frame,
+    eop: &EarthOrientation,
+) -> Result<Ephemeris, PropagationError> {
+    let propagator = sat.propagator.as_ref().map_err(Clone::clone)?;
+    let mut states = Vec::new();
+    for time in TimeSeries::inclusive(start, stop, step) {
+        let prediction = match propagator.propagate(time) {
+            Ok(prediction) => prediction,
+            Err(error) if states.is_empty() => return Err(error),
+            Err(_) => break,
+        };
+        let (position, velocity) = match frame {
+            Frame::Teme => (prediction.position, prediction.velocity),
+            Frame::Itrf => {
+                let ecef = teme_to_ecef_state(prediction.position, prediction.velocity, time, eop);
+                (ecef.position, ecef.velocity)
+            }
+            // the rotation changes slowly enough to apply it to the velocity as it is
+            Frame::Eme2000 => (
+                teme_to_j2000(prediction.position, time),
+                teme_to_j2000(prediction.velocity, time),
+            ),
+        };
+        states.push(State {
+            time,
+            position,
+            velocity,
+        });
+    }
+    let mut comments = vec!["propagated with SGP4".to_string()];
+    if frame == Frame::Itrf {
+        comments.push(format!(
+            "earth orientation UT1-UTC = {} s, polar motion x = {} arcsec, y = {} arcsec",
+            eop.ut1_utc, eop.xp, eop.yp
+        ));
+    }
+    let elements = &sat.elements;
+    Ok(Ephemeris {
+        name: full_name(elements),
+        comments,
+        object_id: elements
+            .international_designator
+            .clone()
+            .unwrap_or_else(|| elements.norad_id.to_string()),
+        states,
+    })
+}
+
+/// "YYYY-MM-DDTHH:MM:SS.mmm" in UTC
+fn ccsds_time(time: Epoch) -> String {
+    let (year, month, day, hour, minute, second, nanos) = time.to_gregorian_utc();
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        nanos / 1_000_000
+    )
+}
+
+/// (keyword, value) pairs of a segment's metadata, in the standard's order
+fn metadata(ephemeris: &Ephemeris, frame: Frame) -> Vec<(&'static str, String)> {
+    let first = ephemeris
+        .states
+        .first()
+        .map_or(String::new(), |s| ccsds_time(s.time));
+    let last = ephemeris
+        .states
+        .last()
+        .map_or(String::new(), |s| ccsds_time(s.time));
+    vec![
+        ("OBJECT_NAME", ephemeris.name.clone()),
+        ("OBJECT_ID", ephemeris.object_id.clone()),
+        ("CENTER_NAME", "EARTH".to_string()),
+        ("REF_FRAME", frame.ccsds_name().to_string()),
+        ("TIME_SYSTEM", "UTC".to_string()),
+        ("START_TIME", first),
+        ("STOP_TIME", last),
+    ]
+}
+
+/// the message in KVN form
+pub fn kvn(ephemerides: &[Ephemeris], frame: Frame, created: Epoch) -> String {
+    let mut out = format!(
+        "CCSDS_OEM_VERS = 3.0\nCREATION_DATE = {}\nORIGINATOR = {}\n",
+        ccsds_time(created),
+        ORIGINATOR
+    );
+    for ephemeris in ephemerides {
+        out.push_str("\nMETA_START\n");
+        for (keyword, value) in metadata(ephemeris, frame) {
+            out.push_str(&format!("{} = {}\n", keyword, value));
+        }
+        out.push_str("META_STOP\n\n");
+        for comment in &ephemeris.comments {
+            out.push_str(&format!("COMMENT {}\n", comment));
+        }
+        for state in &ephemeris.states {
+            let [x, y, z] = state.position;
+            let [vx, vy, vz] = state.velocity;
+            out.push_str(&format!(
+                "{} {:.6} {:.6} {:.6} {:.9} {:.9} {:.9}\n",
+                ccsds_time(state.time),
+                x,
+                y,
+                z,
+                vx,
+                vy,
+                vz
+            ));
+        }
+    }
+    out
+}
+
+/// the message in XML form
+pub fn xml(ephemerides: &[Ephemeris], frame: Frame, created: Epoch) -> String {
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <oem id=\"CCSDS_OEM_VERS\" version=\"3.0\">\n\
+         \x20 <header>\n\
+         \x20   <CREATION_DATE>{}</CREATION_DATE>\n\
+         \x20   <ORIGINATOR>{}</ORIGINATOR>\n\
+         \x20 </header>\n\
+         \x20 <body>\n",
+        ccsds_time(created),
+        ORIGINATOR
+    );
+    for ephemeris in ephemerides {
+        out.push_str("    <segment>\n      <metadata>\n");
+        for (keyword, value) in metadata(ephemeris, frame) {
+            out.push_str(&format!(
+                "        <{0}>{1}</{0}>\n",
+                keyword,
+                xml::escape(&value)
+            ));
+        }
+        out.push_str("      </metadata>\n      <data>\n");
+        for comment in &ephemeris.comments {
+            out.push_str(&format!(
+                "        <COMMENT>{}</COMMENT>\n",
+                xml::escape(comment)
+            ));
+        }
+        for state in &ephemeris.states {
+            out.push_str("        <stateVector>\n");
+            out.push_str(&format!(
+                "          <EPOCH>{}</EPOCH>\n",
+                ccsds_time(state.time)
+            ));
+            for (keyword, value) in ["X", "Y", "Z"].iter().zip(state.position) {
+                out.push_str(&format!("          <{0}>{1:.6}</{0}>\n", keyword, value));
+            }
+            for (keyword, value) in ["X_DOT", "Y_DOT", "Z_DOT"].iter().zip(state.velocity) {
+                out.push_str(&format!("          <{0}>{1:.9}</{0}>\n", keyword, value));
+            }
+            out.push_str("        </stateVector>\n");
+        }
+        out.push_str("      </data>\n    </segment>\n");
+    }
+    out.push_str("  </body>\n</oem>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Ephemeris> {
+        let time = Epoch::from_gregorian_utc(2024, 5, 1, 12, 0, 0, 500_000_000);
+        vec![Ephemeris {
+            name: "KUIPER-P1".to_string(),
+            object_id: "2023-154A".to_string(),
+            comments: vec!["propagated with SGP4".to_string()],
+            states: vec![State {
+                time,
+                position: [6878.1, 0.0, -12.5],
+                velocity: [0.0, 7.6, 0.001],
+            }],
+        }]
+    }
+
+    #[test]
+    fn kvn_layout() {
+        let created = Epoch::from_gregorian_utc_at_midnight(2024, 5, 2);
+        let text = kvn(&sample(), Frame::Eme2000, created);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "CCSDS_OEM_VERS = 3.0");
+        assert_eq!(lines[1], "CREATION_DATE = 2024-05-02T00:00:00.000");
+        assert!(lines.contains(&"REF_FRAME = EME2000"));
+        assert!(lines.contains(&"START_TIME = 2024-05-01T12:00:00.500"));
+        assert_eq!(
+            lines.last(),
+            Some(&"2024-05-01T12:00:00.500 6878.100000 0.000000 -12.500000 0.000000000 7.600000000 0.001000000")
+        );
+    }
+
+    #[test]
+    fn xml_is_balanced() {
+        let created = Epoch::from_gregorian_utc_at_midnight(2024, 5, 2);
+        let text = xml(&sample(), Frame::Teme, created);
+        assert!(text.contains("<REF_FRAME>TEME</REF_FRAME>"));
+        let itrf = xml(&sample(), Frame::Itrf, created);
+        assert!(itrf.contains("<REF_FRAME>ITRF2014</REF_FRAME>"));
+        assert!(text.contains("<X_DOT>0.000000000</X_DOT>"));
+        for tag in [
+            "oem",
+            "header",
+            "body",
+            "segment",
+            "metadata",
+            "data",
+            "stateVector",
+        ] {
+            assert_eq!(
+                text.matches(&format!("<{}>", tag)).count()
+                    + text.matches(&format!("<{} ", tag)).count(),
+                text.matches(&format!("</{}>", tag)).count(),
+                "{}",
+                tag
+            );
+        }
+    }
+
+    #[test]
+    fn itrf_records_earth_orientation() {
+        let sat = Satellite::new(
+            sgp4::Elements::from_tle(
+                None,
+                b"1 58012U 23154A   26288.50000000  .00001000  00000-0  50000-4 0  9992",
+                b"2 58012  51.9000 120.0000 0001000  90.0000 270.0000 15.20000000 10004",
+            )
+            .unwrap(),
+        );
+        let start = Epoch::from_gregorian_utc_at_midnight(2026, 10, 16);
+        let span = (start, start + Unit::Minute * 2, Unit::Minute * 1);
+        let eop = EarthOrientation {
+            ut1_utc: 0.0125,
+            xp: 0.15,
+            yp: 0.3,
+        };
+        let itrf = ephemeris(&sat, span, Frame::Itrf, &eop).unwrap();
+        // without a name the NORAD ID stands in
+        assert_eq!(itrf.name, "58012");
+        assert_eq!(itrf.states.len(), 3);
+        let text = kvn(&[itrf], Frame::Itrf, start);
+        assert!(text.contains("REF_FRAME = ITRF2014\n"));
+        assert!(text.contains(
+            "COMMENT earth orientation UT1-UTC = 0.0125 s, polar motion x = 0.15 arcsec, y = 0.3 arcsec\n"
+        ));
+        let teme = ephemeris(&sat, span, Frame::Teme, &eop).unwrap();
+        assert_eq!(teme.comments, ["propagated with SGP4"]);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{
+    frames::{teme_to_ecef_state, EarthOrientation},
+    observer::{LookAngles, Observer},
+    propagation::Propagator,
+    visibility::{sighting, visual_magnitude},
+};
+use hifitime::prelude::*;
+
+/// coarse search step; a pass shorter than this is found from the elevation peak between
+/// samples instead
+const SEARCH_STEP_SECONDS: f64 = 30.0;
+/// how finely AOS, TCA and LOS are refined
+const REFINE_SECONDS: f64 = 0.5;
+/// how often a pass is checked for optical visibility
+const VISIBILITY_STEP_SECONDS: f64 = 10.0;
+
+/// One pass of a satellite over the observer, above the elevation mask
+#[derive(Clone, Debug)]
+pub struct Pass {
+    pub norad_id: u64,
+    pub name: String,
+    /// acquisition of signal, or the search start if the pass was already in progress
+    pub aos: Epoch,
+    /// time of closest approach, i.e. maximum elevation
+    pub tca: Epoch,
+    /// loss of signal, or the search end if the pass was still in progress
+    pub los: Epoch,
+    pub max_elevation: f64,
+    pub aos_azimuth: f64,
+    pub los_azimuth: f64,
+    /// whether the satellite can be seen sunlit against a dark sky at some point
+    pub visible: bool,
+    /// brightest estimated visual magnitude while visible, `None` if the pass is never
+    /// optically visible or the satellite's standard magnitude is unknown
+    pub magnitude: Option<f64>,
+}
+
+impl Pass {
+    pub fn duration(&self) -> Duration {
+        self.los - self.aos
+    }
+}
+
+/// Pass table sort order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassSort {
+    Aos,
+    MaxElevation,
+    Satellite,
+    Duration,
+}
+
+impl PassSort {
+    pub fn next(self) -> Self {
+        match self {
+            PassSort::Aos => PassSort::MaxElevation,
+            PassSort::MaxElevation => PassSort::Satellite,
+            PassSort::Satellite => PassSort::Duration,
+            PassSort::Duration => PassSort::Aos,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PassSort::Aos => "AOS",
+            PassSort::MaxElevation => "max elevation",
+            PassSort::Satellite => "satellite",
+            PassSort::Duration => "duration",
+        }
+    }
+}
+
+pub fn sort_passes(passes: &mut [Pass], sort: PassSort, reverse: bool) {
+    match sort {
+        PassSort::Aos => passes.sort_by_key(|pass| pass.aos),
+        PassSort::MaxElevation => {
+            passes.sort_by(|a, b| b.max_elevation.total_cmp(&a.max_elevation))
+        }
+        PassSort::Satellite => passes.sort_by(|a, b| a.name.cmp(&b.name).then(a.aos.cmp(&b.aos))),
+        PassSort::Duration => passes.sort_by_key(|pass| std::cmp::Reverse(pass.duration())),
+    }
+    if reverse {
+        passes.reverse();
+    }
+}
+
+pub fn look_at(
+    propagator: &Propagator,
+    observer: &Observer,
+    eop: &EarthOrientation,
+    time: Epoch,
+) -> Option<LookAngles> {
+    let prediction = propagator.propagate(time).ok()?;
+    let ecef = teme_to_ecef_state(prediction.position, prediction.velocity, time, eop);
+    Some(observer.look_angles(&ecef))
+}
+
+/// look angles every `step` from AOS to LOS, for plotting a pass in the sky
+pub fn pass_track(
+    propagator: &Propagator,
+    observer: &Observer,
+    eop: &EarthOrientation,
+    pass: &Pass,
+    step: Duration,
+) -> Vec<LookAngles> {
+    TimeSeries::inclusive(pass.aos, pass.los, step)
+        .chain(std::iter::once(pass.los))
+        .filter_map(|time| look_at(propagator, observer, eop, time))
+        .collect()
+}
+
+/// Finds every pass between `start` and `end` that rises above `min_elevation` degrees,
+/// with magnitudes from `standard_magnitude` if known. The search stops early if the
+/// satellite can no longer be propagated.
+pub fn predict_passes(
+    propagator: &Propagator,
+    observer: &Observer,
+    eop: &EarthOrientation,
+    (start, end): (Epoch, Epoch),
+    min_elevation: f64,
+    object: (u64, &str),
+    standard_magnitude: Option<f64>,
+) -> Vec<Pass> {
+    let elevation = |time: Epoch| look_at(propagator, observer, eop, time).map(|l| l.elevation);
+    let step = Unit::Second * SEARCH_STEP_SECONDS;
+
+    let mut passes = Vec::new();
+    let Some(mut previous) = elevation(start) else {
+        return passes;
+    };
+    let mut aos = (previous >= min_elevation).then_some(start);
+    // the sample before `previous`, to spot elevation peaks
+    let mut earlier: Option<(Epoch, f64)> = None;
+    let mut time = start;
+    while time < end {
+        let next_time = (time + step).min(end);
+        let Some(current) = elevation(next_time) else {
+            break;
+        };
+        if previous < min_elevation && current >= min_elevation {
+            aos = Some(refine_crossing(&elevation, time, next_time, min_elevation));
+        } else if previous >= min_elevation && current < min_elevation {
+            if let Some(rise) = aos.take() {
+                let set = refine_crossing(&elevation, time, next_time, min_elevation);
+                passes.extend(build_pass(
+                    propagator,
+                    observer,
+                    eop,
+                    (rise, set),
+                    object,
+                    standard_magnitude,
+                ));
+            }
+        } else if let Some((earlier_time, _)) = earlier.filter(|&(_, earlier)| {
+            current < min_elevation && earlier < previous && previous > current
+        }) {
+            // three samples below the mask around a peak, which may still poke above it
+            let peak = highest(&elevation, earlier_time, next_time)
+                .filter(|&peak| elevation(peak).is_some_and(|e| e >= min_elevation));
+            if let Some(tca) = peak {
+                let rise = refine_crossing(&elevation, earlier_time, tca, min_elevation);
+                let set = refine_crossing(&elevation, tca, next_time, min_elevation);
+                passes.extend(build_pass(
+                    propagator,
+                    observer,
+                    eop,
+                    (rise, set),
+                    object,
+                    standard_magnitude,
+                ));
+            }
+        }
+        earlier = Some((time, previous));
+        previous = current;
+        time = next_time;
+    }
+    if let Some(rise) = aos {
+        passes.extend(build_pass(
+            propagator,
+            observer,
+            eop,
+            (rise, end),
+            object,
+            standard_magnitude,
+        ));
+    }
+    passes
+}
+
+/// bisects for the time elevation crosses `threshold` between `before` and `after`
+fn refine_crossing(
+    elevation: &impl Fn(Epoch) -> Option<f64>,
+    mut before: Epoch,
+    mut after: Epoch,
+    threshold: f64,
+) -> Epoch {
+    let rising = elevation(before).unwrap_or(f64::MIN) < threshold;
+    while (after - before).to_seconds() > REFINE_SECONDS {
+        let middle = before + (after - before) / 2;
+        let above = elevation(middle).is_some_and(|e| e >= threshold);
+        if above == rising {
+            after = middle;
+        } else {
+            before = middle;
+        }
+    }
+    before + (after - before) / 2
+}
+
+/// golden section search for the time of maximum elevation between `low` and `high`,
+/// where it is unimodal, e.g. within a pass
+fn highest(
+    elevation: &impl Fn(Epoch) -> Option<f64>,
+    mut low: Epoch,
+    mut high: Epoch,
+) -> Option<Epoch> {
+    let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+    while (high - low).to_seconds() > REFINE_SECONDS {
+        let span = high - low;
+        let a = high - span * ratio;
+        let b = low + span * ratio;
+        if elevation(a)? < elevation(b)? {
+            low = a;
+        } else {
+            high = b;
+        }
+    }
+    Some(low + (high - low) / 2)
+}
+
+fn build_pass(
+    propagator: &Propagator,
+    observer: &Observer,
+    eop: &EarthOrientation,
+    (aos, los): (Epoch, Epoch),
+    (norad_id, name): (u64, &str),
+    standard_magnitude: Option<f64>,
+) -> Option<Pass> {
+    let look = |time: Epoch| look_at(propagator, observer, eop, time);
+    let tca = highest(&|time| look(time).map(|l| l.elevation), aos, los)?;
+    let sightings: Vec<_> = TimeSeries::inclusive(aos, los, Unit::Second * VISIBILITY_STEP_SECONDS)
+        .chain([tca, los])
+        .filter_map(|time| sighting(propagator, observer, eop, time))
+        .collect();
+    let magnitude = standard_magnitude.and_then(|standard| {
+        sightings
+            .iter()
+            .map(|seen| visual_magnitude(standard, seen.range, seen.phase))
+            .reduce(f64::min)
+    });
+    Some(Pass {
+        norad_id,
+        name: name.to_string(),
+        aos,
+        tca,
+        los,
+        max_elevation: look(tca)?.elevation,
+        aos_azimuth: look(aos)?.azimuth,
+        los_azimuth: look(los)?.azimuth,
+        visible: !sightings.is_empty(),
+        magnitude,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::GroundPos;
+
+    fn start() -> Epoch {
+        Epoch::from_gregorian_utc_at_midnight(2020, 7, 13)
+    }
+
+    /// a day of ISS passes over Delft
+    fn iss_over_delft(min_elevation: f64) -> Vec<Pass> {
+        let elements = sgp4::Elements::from_tle(
+            Some("ISS".to_string()),
+            b"1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992",
+            b"2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008",
+        )
+        .unwrap();
+        let observer = Observer::new(GroundPos {
+            lat: 52.0,
+            lon: 4.37,
+            alt: 0.0,
+        });
+        predict_passes(
+            &Propagator::new(&elements).unwrap(),
+            &observer,
+            &EarthOrientation::default(),
+            (start(), start() + Unit::Day * 1),
+            min_elevation,
+            (25544, "ISS"),
+            None,
+        )
+    }
+
+    #[test]
+    fn matches_reference() {
+        // the same SGP4 states sampled every second through a separate IAU-82 GMST and
+        // WGS-84 look angle implementation, with crossings and peaks interpolated:
+        // seconds after midnight of AOS, TCA and LOS, max elevation, AOS and LOS azimuth
+        let expected = [
+            (2305.320, 2507.682, 2710.161, 85.5184, 264.499, 88.341),
+            (8111.935, 8311.939, 8511.424, 61.6895, 277.139, 115.596),
+            (13941.484, 14093.336, 14244.687, 19.8441, 264.169, 166.434),
+            (80057.123, 80239.694, 80422.926, 32.2548, 216.858, 86.947),
+            (85830.942, 86032.887, 86235.111, 79.2940, 257.657, 85.031),
+        ];
+        let passes = iss_over_delft(10.0);
+        assert_eq!(passes.len(), expected.len());
+        let since = |time: Epoch| (time - start()).to_seconds();
+        for (pass, (aos, tca, los, max_elevation, aos_azimuth, los_azimuth)) in
+            passes.iter().zip(expected)
+        {
+            assert!((since(pass.aos) - aos).abs() < 1.0, "{:?}", pass);
+            assert!((since(pass.tca) - tca).abs() < 1.0, "{:?}", pass);
+            assert!((since(pass.los) - los).abs() < 1.0, "{:?}", pass);
+            assert!(
+                (pass.max_elevation - max_elevation).abs() < 0.01,
+                "{:?}",
+                pass
+            );
+            assert!((pass.aos_azimuth - aos_azimuth).abs() < 0.1, "{:?}", pass);
+            assert!((pass.los_azimuth - los_azimuth).abs() < 0.1, "{:?}", pass);
+        }
+    }
+
+    #[test]
+    fn finds_passes_shorter_than_the_step() {
+        // the third pass above peaks at 19.844°, so it clears this mask only briefly
+        let passes = iss_over_delft(19.84);
+        let brief = passes
+            .iter()
+            .find(|pass| ((pass.tca - start()).to_seconds() - 14093.3).abs() < 1.0)
+            .expect("the pass peaking at 19.844° is found");
+        assert!(brief.duration() < Unit::Second * SEARCH_STEP_SECONDS);
+        assert!(brief.max_elevation >= 19.84);
+    }
+}
@@ -0,0 +1,172 @@
+//! `--positions`: where the selected satellites are at one moment, printed for scripts
+
+use crate::{
+    clock,
+    frames::{ecef_to_geodetic, teme_to_ecef_state, EarthOrientation},
+    satellite::Satellite,
+    selection::full_name,
+};
+use hifitime::prelude::*;
+use serde::Serialize;
+
+/// How `--positions` prints
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "table" => Some(Format::Table),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// One satellite's position at the requested time
+#[derive(Clone, Debug, Serialize)]
+pub struct Position {
+    pub name: String,
+    pub norad_id: u64,
+    /// UTC, ISO 8601
+    pub time: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// km above the WGS-84 ellipsoid
+    pub alt: f64,
+    /// inertial speed in km/s
+    pub speed: f64,
+}
+
+/// positions of `satellites` at `time`; those that can't be propagated are left out and
+/// reported on stderr so the output stays parseable
+pub fn positions<'a>(
+    satellites: impl Iterator<Item = &'a Satellite>,
+    time: Epoch,
+    eop: &EarthOrientation,
+) -> Vec<Position> {
+    satellites
+        .filter_map(|sat| {
+            let prediction = sat
+                .propagator
+                .as_ref()
+                .map_err(Clone::clone)
+                .and_then(|propagator| propagator.propagate(time));
+            let prediction = match prediction {
+                Ok(prediction) => prediction,
+                Err(error) => {
+                    eprintln!("warning: {}: {}", sat.elements.norad_id, error);
+                    return None;
+                }
+            };
+            let ecef = teme_to_ecef_state(prediction.position, prediction.velocity, time, eop);
+            let ground = ecef_to_geodetic(ecef.position);
+            Some(Position {
+                name: full_name(&sat.elements),
+                norad_id: sat.elements.norad_id,
+                time: clock::format_iso(time),
+                lat: ground.lat,
+                lon: ground.lon,
+                alt: ground.alt,
+                speed: prediction
+                    .velocity
+                    .iter()
+                    .map(|v| v.powi(2))
+                    .sum::<f64>()
+                    .sqrt(),
+            })
+        })
+        .collect()
+}
+
+/// `positions` as text in `format`, ending in a newline
+pub fn render(positions: &[Position], format: Format) -> anyhow::Result<String> {
+    let mut out = String::new();
+    match format {
+        Format::Table => {
+            out.push_str(&format!(
+                "{:<24} {:>6}  {:<19}  {:>8}  {:>9}  {:>7}  {:>6}\n",
+                "satellite", "norad", "time (UTC)", "lat", "lon", "alt km", "km/s"
+            ));
+            for position in positions {
+                out.push_str(&format!(
+                    "{:<24} {:>6}  {:<19}  {:>8.3}  {:>9.3}  {:>7.1}  {:>6.3}\n",
+                    position.name,
+                    position.norad_id,
+                    position.time.trim_end_matches('Z').replace('T', " "),
+                    position.lat,
+                    position.lon,
+                    position.alt,
+                    position.speed,
+                ));
+            }
+        }
+        Format::Json => {
+            out.push_str(&serde_json::to_string_pretty(positions)?);
+            out.push('\n');
+        }
+        Format::Csv => {
+            out.push_str("name,norad_id,time,lat,lon,alt,speed\n");
+            for position in positions {
+                out.push_str(&format!(
+                    "{},{},{},{:.6},{:.6},{:.3},{:.6}\n",
+                    csv_field(&position.name),
+                    position.norad_id,
+                    position.time,
+                    position.lat,
+                    position.lon,
+                    position.alt,
+                    position.speed,
+                ));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// quotes a CSV field if it needs it
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let positions = [Position {
+            name: "KUIPER, P1".to_string(),
+            norad_id: 58012,
+            time: "2026-10-16T03:00:00Z".to_string(),
+            lat: -1.5,
+            lon: 120.25,
+            alt: 506.7,
+            speed: 7.612,
+        }];
+        let csv = render(&positions, Format::Csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("\"KUIPER, P1\",58012,2026-10-16T03:00:00Z,-1.500000,120.250000,506.700,7.612000")
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&positions, Format::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["norad_id"], 58012);
+        assert_eq!(json[0]["lon"], 120.25);
+        let table = render(&positions, Format::Table).unwrap();
+        assert!(table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .contains("2026-10-16 03:00:00"));
+    }
+}
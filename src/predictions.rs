@@ -0,0 +1,147 @@
+use crate::{
+    eclipse::{eclipse_events, EclipseEvent},
+    frames::EarthOrientation,
+    observer::Observer,
+    passes::{predict_passes, Pass},
+    satellite::Satellite,
+    selection::full_name,
+    visibility::StandardMagnitudes,
+};
+use hifitime::prelude::*;
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+/// every pass of every satellite over the observer between the window's start and end
+pub fn passes(
+    satellites: &[Satellite],
+    observer: &Observer,
+    eop: &EarthOrientation,
+    window: (Epoch, Epoch),
+    min_elevation: f64,
+    magnitudes: &StandardMagnitudes,
+) -> Vec<Pass> {
+    satellites
+        .iter()
+        .filter_map(|sat| Some((sat, sat.propagator.as_ref().ok()?)))
+        .flat_map(|(sat, propagator)| {
+            predict_passes(
+                propagator,
+                observer,
+                eop,
+                window,
+                min_elevation,
+                (sat.elements.norad_id, &full_name(&sat.elements)),
+                magnitudes.of(sat.elements.norad_id),
+            )
+        })
+        .collect()
+}
+
+/// every shadow entry and exit of every satellite between the window's start and end, in
+/// time order
+pub fn eclipses(satellites: &[Satellite], window: (Epoch, Epoch)) -> Vec<EclipseEvent> {
+    let mut events: Vec<EclipseEvent> = satellites
+        .iter()
+        .filter_map(|sat| Some((sat, sat.propagator.as_ref().ok()?)))
+        .flat_map(|(sat, propagator)| {
+            eclipse_events(
+                propagator,
+                window,
+                (sat.elements.norad_id, &full_name(&sat.elements)),
+            )
+        })
+        .collect();
+    events.sort_by_key(|event| event.time);
+    events
+}
+
+/// Passes and eclipses over one window, for the element load numbered `generation`
+pub struct Predictions {
+    pub generation: u64,
+    pub window: (Epoch, Epoch),
+    /// unsorted
+    pub passes: Vec<Pass>,
+    pub eclipses: Vec<EclipseEvent>,
+}
+
+struct Request {
+    generation: u64,
+    satellites: Arc<Vec<Satellite>>,
+    window: (Epoch, Epoch),
+}
+
+/// Computes passes and eclipses on a worker thread, so that the UI keeps drawing while a
+/// window of several days is searched
+pub struct Predictor {
+    tx: Sender<Request>,
+    rx: Receiver<Predictions>,
+}
+
+impl Predictor {
+    /// Starts the worker, which waits for requests. Passes are only predicted with an
+    /// `observer`.
+    pub fn spawn(
+        observer: Option<Observer>,
+        eop: EarthOrientation,
+        min_elevation: f64,
+        magnitudes: StandardMagnitudes,
+    ) -> Self {
+        let (tx, requests) = mpsc::channel::<Request>();
+        let (results, rx) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut request) = requests.recv() {
+                // the clock may have moved on several windows while the last one was searched
+                while let Ok(newer) = requests.try_recv() {
+                    request = newer;
+                }
+                let satellites = &request.satellites;
+                let passes = observer.map_or(Vec::new(), |observer| {
+                    passes(
+                        satellites,
+                        &observer,
+                        &eop,
+                        request.window,
+                        min_elevation,
+                        &magnitudes,
+                    )
+                });
+                let predictions = Predictions {
+                    generation: request.generation,
+                    window: request.window,
+                    passes,
+                    eclipses: eclipses(satellites, request.window),
+                };
+                if results.send(predictions).is_err() {
+                    return;
+                }
+            }
+        });
+        Predictor { tx, rx }
+    }
+
+    /// asks for the passes and eclipses of `satellites` over `window`, replacing any request
+    /// not yet started
+    pub fn request(
+        &self,
+        generation: u64,
+        satellites: Arc<Vec<Satellite>>,
+        window: (Epoch, Epoch),
+    ) {
+        // the worker only stops once this end has been dropped
+        let _ = self.tx.send(Request {
+            generation,
+            satellites,
+            window,
+        });
+    }
+
+    /// the next finished window, if any, without blocking
+    pub fn poll(&self) -> Option<Predictions> {
+        self.rx.try_recv().ok()
+    }
+}
@@ -0,0 +1,250 @@
+//! Map projections between latitude/longitude and canvas coordinates. Canvas units are
+//! degrees of arc at the projection's standard point, so every projection is about 360
+//! units across for the whole earth.
+
+use crate::world::{WORLD_HIGH_RESOLUTION, WORLD_LOW_RESOLUTION};
+use ratatui::widgets::canvas::MapResolution;
+
+/// Mercator runs off to infinity at the poles, so it stops where the map becomes square
+const MERCATOR_MAX_LAT: f64 = 85.05113;
+/// the polar views show their own hemisphere and this far into the other one
+const POLAR_MIN_LAT: f64 = -60.0;
+
+/// How the globe is flattened onto the map canvas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// longitude and latitude as x and y
+    Equirectangular,
+    Mercator,
+    /// the globe seen from far away above (`lat`, `lon`)
+    Orthographic {
+        lat: f64,
+        lon: f64,
+    },
+    /// azimuthal equidistant around the north pole
+    NorthPolar,
+    /// azimuthal equidistant around the south pole
+    SouthPolar,
+}
+
+impl Projection {
+    pub fn name(self) -> &'static str {
+        match self {
+            Projection::Equirectangular => "equirectangular",
+            Projection::Mercator => "mercator",
+            Projection::Orthographic { .. } => "globe",
+            Projection::NorthPolar => "north polar",
+            Projection::SouthPolar => "south polar",
+        }
+    }
+
+    /// "equirectangular", "mercator", "globe", "north" or "south"
+    pub fn from_name(name: &str, globe: (f64, f64)) -> Option<Self> {
+        [
+            Projection::Equirectangular,
+            Projection::Mercator,
+            Projection::Orthographic {
+                lat: globe.0,
+                lon: globe.1,
+            },
+            Projection::NorthPolar,
+            Projection::SouthPolar,
+        ]
+        .into_iter()
+        .find(|projection| projection.name().split(' ').next() == Some(name))
+    }
+
+    /// the projection after this one, an orthographic one centered on `globe`
+    pub fn next(self, globe: (f64, f64)) -> Self {
+        match self {
+            Projection::Equirectangular => Projection::Mercator,
+            Projection::Mercator => Projection::Orthographic {
+                lat: globe.0,
+                lon: globe.1,
+            },
+            Projection::Orthographic { .. } => Projection::NorthPolar,
+            Projection::NorthPolar => Projection::SouthPolar,
+            Projection::SouthPolar => Projection::Equirectangular,
+        }
+    }
+
+    /// whether the whole earth is drawn as a disc, which has to stay round on screen
+    pub fn is_round(self) -> bool {
+        !matches!(self, Projection::Equirectangular | Projection::Mercator)
+    }
+
+    /// radius of the disc for round projections
+    pub fn radius(self) -> f64 {
+        match self {
+            Projection::Orthographic { .. } => 90.0,
+            _ => 90.0 - POLAR_MIN_LAT,
+        }
+    }
+
+    /// canvas x and y range covering the whole earth
+    pub fn extent(self) -> ([f64; 2], [f64; 2]) {
+        match self {
+            Projection::Equirectangular => ([-180.0, 180.0], [-90.0, 90.0]),
+            Projection::Mercator => ([-180.0, 180.0], [-180.0, 180.0]),
+            _ => {
+                let r = self.radius();
+                ([-r, r], [-r, r])
+            }
+        }
+    }
+
+    /// canvas position of a point in degrees, `None` where the projection doesn't show it
+    pub fn project(self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        match self {
+            Projection::Equirectangular => Some((lon, lat)),
+            Projection::Mercator => {
+                if lat.abs() > MERCATOR_MAX_LAT {
+                    return None;
+                }
+                let y = (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0)
+                    .tan()
+                    .ln();
+                Some((lon, y.to_degrees()))
+            }
+            Projection::Orthographic {
+                lat: lat0,
+                lon: lon0,
+            } => {
+                let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+                let (sin_lat0, cos_lat0) = lat0.to_radians().sin_cos();
+                let (sin_dlon, cos_dlon) = (lon - lon0).to_radians().sin_cos();
+                // cosine of the angle from the center, negative on the far side
+                if sin_lat0 * sin_lat + cos_lat0 * cos_lat * cos_dlon < 0.0 {
+                    return None;
+                }
+                let r = self.radius();
+                Some((
+                    r * cos_lat * sin_dlon,
+                    r * (cos_lat0 * sin_lat - sin_lat0 * cos_lat * cos_dlon),
+                ))
+            }
+            Projection::NorthPolar => {
+                if lat < POLAR_MIN_LAT {
+                    return None;
+                }
+                let (sin, cos) = lon.to_radians().sin_cos();
+                let r = 90.0 - lat;
+                Some((r * sin, -r * cos))
+            }
+            Projection::SouthPolar => {
+                if -lat < POLAR_MIN_LAT {
+                    return None;
+                }
+                let (sin, cos) = lon.to_radians().sin_cos();
+                let r = 90.0 + lat;
+                Some((r * sin, r * cos))
+            }
+        }
+    }
+
+    /// canvas lines for a short step between two (lat, lon) points: none if either end is
+    /// hidden, two if the step crosses the antimeridian of a flat map, one otherwise
+    pub fn segments(self, from: (f64, f64), to: (f64, f64)) -> Vec<(f64, f64, f64, f64)> {
+        let (Some((x1, y1)), Some((x2, y2))) =
+            (self.project(from.0, from.1), self.project(to.0, to.1))
+        else {
+            return Vec::new();
+        };
+        let crossing = antimeridian_crossing(from, to);
+        let Some((lat, edge)) = crossing.filter(|_| !self.is_round()) else {
+            return vec![(x1, y1, x2, y2)];
+        };
+        match (self.project(lat, edge), self.project(lat, -edge)) {
+            (Some((xa, ya)), Some((xb, yb))) => vec![(x1, y1, xa, ya), (xb, yb, x2, y2)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// the coastline, projected
+    pub fn coastline(self, resolution: MapResolution) -> Vec<(f64, f64)> {
+        let data: &[(f64, f64)] = match resolution {
+            MapResolution::Low => &WORLD_LOW_RESOLUTION,
+            MapResolution::High => &WORLD_HIGH_RESOLUTION,
+        };
+        data.iter()
+            .filter_map(|&(lon, lat)| self.project(lat, lon))
+            .collect()
+    }
+}
+
+/// Where a short step between two (lat, lon) points crosses the antimeridian, if it does:
+/// the latitude there and the longitude, 180 or -180, on the side of `from`
+pub fn antimeridian_crossing(from: (f64, f64), to: (f64, f64)) -> Option<(f64, f64)> {
+    if (to.1 - from.1).abs() <= 180.0 {
+        return None;
+    }
+    // unwrap the longitude to find where the step meets ±180
+    let (edge, to_lon) = if from.1 > 0.0 {
+        (180.0, to.1 + 360.0)
+    } else {
+        (-180.0, to.1 - 360.0)
+    };
+    let fraction = (edge - from.1) / (to_lon - from.1);
+    Some((from.0 + (to.0 - from.0) * fraction, edge))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    #[test]
+    fn projections() {
+        assert!(close(
+            Projection::Mercator.project(0.0, 45.0).unwrap(),
+            (45.0, 0.0)
+        ));
+        // mercator is conformal: at 60° north a degree of latitude is twice as long
+        let a = Projection::Mercator.project(60.0, 0.0).unwrap().1;
+        let b = Projection::Mercator.project(60.001, 0.0).unwrap().1;
+        assert!(((b - a) / 0.001 - 2.0).abs() < 1e-3);
+
+        let globe = Projection::Orthographic {
+            lat: 47.6,
+            lon: -122.3,
+        };
+        assert!(close(globe.project(47.6, -122.3).unwrap(), (0.0, 0.0)));
+        assert!(globe.project(-47.6, 57.7).is_none());
+        // the north pole is straight up from a northern center
+        let pole = globe.project(90.0, 0.0).unwrap();
+        assert!(pole.0.abs() < 1e-9 && pole.1 > 0.0);
+
+        assert!(close(
+            Projection::NorthPolar.project(0.0, 90.0).unwrap(),
+            (90.0, 0.0)
+        ));
+        assert!(close(
+            Projection::SouthPolar.project(-90.0, 0.0).unwrap(),
+            (0.0, 0.0)
+        ));
+        assert!(Projection::NorthPolar.project(-70.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn segments_split_at_antimeridian() {
+        let map = Projection::Equirectangular;
+        assert_eq!(
+            map.segments((0.0, 179.0), (2.0, -179.0)),
+            vec![(179.0, 0.0, 180.0, 1.0), (-180.0, 1.0, -179.0, 2.0)]
+        );
+        assert_eq!(map.segments((0.0, 10.0), (0.0, 12.0)).len(), 1);
+        // round projections have no seam there
+        assert_eq!(
+            Projection::NorthPolar
+                .segments((60.0, 179.0), (60.0, -179.0))
+                .len(),
+            1
+        );
+        assert!(Projection::Mercator
+            .segments((89.0, 0.0), (80.0, 0.0))
+            .is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use core::str::FromStr;
+use hifitime::prelude::*;
+use sgp4::{Elements, Prediction};
+use std::fmt;
+
+/// element sets older (or newer) than this are too far from their epoch for SGP4 to be trusted
+pub const MAX_EPOCH_AGE_DAYS: f64 = 30.0;
+
+/// below this geocentric radius (km) the satellite would be inside the atmosphere / earth
+pub const DECAY_RADIUS_KM: f64 = 6378.137 + 80.0;
+
+/// Why a satellite couldn't be propagated
+#[derive(Debug, Clone)]
+pub enum PropagationError {
+    /// the element set epoch couldn't be turned into a hifitime epoch
+    BadEpoch(String),
+    NegativeMeanMotion(f64),
+    /// elements rejected by sgp4 when building the propagator constants
+    BadElements(sgp4::ElementsError),
+    EpochTooFar {
+        days: f64,
+    },
+    /// sgp4 gave up during propagation
+    Propagation(sgp4::Error),
+    /// propagated below [`DECAY_RADIUS_KM`]
+    Decayed {
+        radius_km: f64,
+    },
+}
+
+impl fmt::Display for PropagationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropagationError::BadEpoch(epoch) => write!(f, "unreadable epoch {}", epoch),
+            PropagationError::NegativeMeanMotion(mean_motion) => {
+                write!(f, "negative mean motion {} rev/day", mean_motion)
+            }
+            PropagationError::BadElements(error) => write!(f, "bad elements: {}", error),
+            PropagationError::EpochTooFar { days } => {
+                write!(f, "epoch {:.1} days away", days)
+            }
+            PropagationError::Propagation(error) => write!(f, "sgp4: {}", error),
+            PropagationError::Decayed { radius_km } => {
+                write!(f, "decayed ({:.0} km from earth center)", radius_km)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PropagationError {}
+
+impl PropagationError {
+    /// one or two words for the side list
+    pub fn short(&self) -> &'static str {
+        match self {
+            PropagationError::BadEpoch(_) => "bad epoch",
+            PropagationError::NegativeMeanMotion(_) => "neg. mean motion",
+            PropagationError::BadElements(_) => "bad elements",
+            PropagationError::EpochTooFar { .. } => "stale elements",
+            PropagationError::Propagation(_) => "sgp4 failure",
+            PropagationError::Decayed { .. } => "decayed",
+        }
+    }
+}
+
+/// SGP4 constants and epoch for one element set, built once so that each sample is just a
+/// `propagate` call
+pub struct Propagator {
+    pub epoch: Epoch,
+    constants: sgp4::Constants,
+}
+
+impl Propagator {
+    /// Based on https://github.com/colej4/satapp/blob/be4a3831134475396bab3639b8add1b337e5b93c/src-tauri/src/tracking.rs#L60-L77
+    pub fn new(elements: &Elements) -> Result<Self, PropagationError> {
+        let epoch = Epoch::from_str(format!("{} UTC", elements.datetime).as_str())
+            .map_err(|_| PropagationError::BadEpoch(elements.datetime.to_string()))?;
+        if elements.mean_motion <= 0.0 {
+            return Err(PropagationError::NegativeMeanMotion(elements.mean_motion));
+        }
+        let constants =
+            sgp4::Constants::from_elements(elements).map_err(PropagationError::BadElements)?;
+        Ok(Propagator { epoch, constants })
+    }
+
+    /// TEME position (km) and velocity (km/s) at `time`
+    pub fn propagate(&self, time: Epoch) -> Result<Prediction, PropagationError> {
+        let duration = time - self.epoch;
+        let days = duration.to_unit(Unit::Day);
+        if days.abs() > MAX_EPOCH_AGE_DAYS {
+            return Err(PropagationError::EpochTooFar { days });
+        }
+        let prediction = self
+            .constants
+            .propagate(sgp4::MinutesSinceEpoch(duration.to_seconds() / 60.0))
+            .map_err(PropagationError::Propagation)?;
+        let radius_km = prediction
+            .position
+            .iter()
+            .map(|p| p.powi(2))
+            .sum::<f64>()
+            .sqrt();
+        if radius_km < DECAY_RADIUS_KM {
+            return Err(PropagationError::Decayed { radius_km });
+        }
+        Ok(prediction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(line2: &[u8]) -> Elements {
+        Elements::from_tle(
+            None,
+            b"1 58012U 23154A   26288.50000000  .00001000  00000-0  50000-4 0  9992",
+            line2,
+        )
+        .unwrap()
+    }
+
+    fn kuiper() -> Elements {
+        elements(b"2 58012  51.9000 120.0000 0001000  90.0000 270.0000 15.20000000 10004")
+    }
+
+    #[test]
+    fn rejects_mean_motion_without_period() {
+        let mut elements = kuiper();
+        elements.mean_motion = 0.0;
+        assert!(matches!(
+            Propagator::new(&elements),
+            Err(PropagationError::NegativeMeanMotion(_))
+        ));
+        elements.mean_motion = -15.2;
+        assert!(matches!(
+            Propagator::new(&elements),
+            Err(PropagationError::NegativeMeanMotion(_))
+        ));
+    }
+
+    #[test]
+    fn epoch_age_limit() {
+        let propagator = Propagator::new(&kuiper()).unwrap();
+        let within = Unit::Day * (MAX_EPOCH_AGE_DAYS - 0.1);
+        let beyond = Unit::Day * (MAX_EPOCH_AGE_DAYS + 0.1);
+        assert!(propagator.propagate(propagator.epoch + within).is_ok());
+        assert!(propagator.propagate(propagator.epoch - within).is_ok());
+        match propagator.propagate(propagator.epoch + beyond) {
+            Err(PropagationError::EpochTooFar { days }) => assert!(days > MAX_EPOCH_AGE_DAYS),
+            _ => panic!("expected the elements to be too old"),
+        }
+        match propagator.propagate(propagator.epoch - beyond) {
+            Err(PropagationError::EpochTooFar { days }) => assert!(days < -MAX_EPOCH_AGE_DAYS),
+            _ => panic!("expected the elements to be too new"),
+        }
+    }
+
+    #[test]
+    fn decayed_below_the_atmosphere() {
+        // 17 revolutions a day is a semi-major axis of about 6390 km, inside the earth
+        let propagator = Propagator::new(&elements(
+            b"2 58012  51.9000 120.0000 0001000  90.0000 270.0000 17.00000000 10004",
+        ))
+        .unwrap();
+        match propagator.propagate(propagator.epoch) {
+            Err(PropagationError::Decayed { radius_km }) => assert!(radius_km < DECAY_RADIUS_KM),
+            Err(error) => panic!("unexpected {}", error),
+            Ok(_) => panic!("expected a decayed orbit"),
+        }
+    }
+}
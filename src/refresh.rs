@@ -0,0 +1,94 @@
+use crate::{
+    cache::Cache,
+    source::{ElementSource, Loaded},
+};
+use hifitime::prelude::*;
+use sgp4::Elements;
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration as StdDuration,
+};
+
+/// how soon to try again after a failed refresh, if the regular interval is longer
+const RETRY_AFTER: StdDuration = StdDuration::from_secs(10 * 60);
+
+/// Outcome of one load attempt on the worker thread
+pub enum RefreshEvent {
+    Loaded {
+        elements: Vec<Elements>,
+        /// files of a directory source left out, see [`Loaded::skipped`]
+        skipped: Vec<String>,
+        at: Epoch,
+    },
+    Failed {
+        error: String,
+        at: Epoch,
+    },
+}
+
+/// Loads elements on a worker thread, optionally repeating on an interval
+pub struct Refresher {
+    rx: Receiver<RefreshEvent>,
+    interval: Option<StdDuration>,
+}
+
+impl Refresher {
+    /// Starts the worker. With `already_loaded` the first load is skipped and the worker
+    /// waits one interval before reloading; without an interval it loads at most once.
+    pub fn spawn(
+        source: ElementSource,
+        cache: Option<Cache>,
+        interval: Option<StdDuration>,
+        already_loaded: bool,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut wait = already_loaded.then_some(interval).flatten();
+            if already_loaded && wait.is_none() {
+                return;
+            }
+            loop {
+                if let Some(wait) = wait {
+                    thread::sleep(wait);
+                }
+                let at = Epoch::now().unwrap();
+                let event = match source.load() {
+                    Ok(Loaded { elements, skipped }) => {
+                        if let Some(cache) = &cache {
+                            // a read-only cache directory shouldn't keep the fresh elements off the screen
+                            let _ = cache.store(&elements, at);
+                        }
+                        wait = interval;
+                        RefreshEvent::Loaded {
+                            elements,
+                            skipped,
+                            at,
+                        }
+                    }
+                    Err(error) => {
+                        wait = interval.map(|interval| interval.min(RETRY_AFTER));
+                        RefreshEvent::Failed {
+                            error: format!("{:#}", error),
+                            at,
+                        }
+                    }
+                };
+                // stop once the UI has gone away or there is nothing left to schedule
+                if tx.send(event).is_err() || wait.is_none() {
+                    return;
+                }
+            }
+        });
+        Refresher { rx, interval }
+    }
+
+    /// the next finished load, if any, without blocking
+    pub fn poll(&self) -> Option<RefreshEvent> {
+        self.rx.try_recv().ok()
+    }
+
+    pub fn interval(&self) -> Option<StdDuration> {
+        self.interval
+    }
+}
@@ -0,0 +1,31 @@
+use crate::propagation::{PropagationError, Propagator};
+use hifitime::prelude::*;
+use sgp4::Elements;
+
+/// A tracked satellite: its element set and the propagator built from it
+pub struct Satellite {
+    pub elements: Elements,
+    pub propagator: Result<Propagator, PropagationError>,
+}
+
+impl Satellite {
+    pub fn new(elements: Elements) -> Self {
+        let propagator = Propagator::new(&elements);
+        Satellite {
+            elements,
+            propagator,
+        }
+    }
+
+    /// days from the element set epoch to `time`, if the epoch could be read
+    pub fn element_age(&self, time: Epoch) -> Option<f64> {
+        let propagator = self.propagator.as_ref().ok()?;
+        Some((time - propagator.epoch).to_unit(Unit::Day))
+    }
+
+    /// orbital period from the mean motion, `None` if the mean motion is unusable
+    pub fn period(&self) -> Option<Duration> {
+        let mean_motion = self.elements.mean_motion;
+        (mean_motion > 0.0).then(|| Unit::Day * (1.0 / mean_motion))
+    }
+}
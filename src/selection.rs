@@ -0,0 +1,297 @@
+use regex::Regex;
+use sgp4::Elements;
+
+/// Which satellites to track.
+///
+/// Groups, international designators and NORAD IDs pick the candidate set (any of them
+/// matching is enough); name globs and regular expressions then narrow it down (again,
+/// any of them matching is enough).
+#[derive(Clone, Default)]
+pub struct Selection {
+    /// CelesTrak GROUP names, e.g. "active" or "starlink"
+    pub groups: Vec<String>,
+    /// international designators or launch prefixes, e.g. "2023-154"
+    pub intdes: Vec<String>,
+    pub norad_ids: Vec<u64>,
+    /// case-insensitive globs matched against the object name
+    pub names: Vec<String>,
+    /// regular expressions searched for in the object name, built case-insensitive
+    pub name_regexes: Vec<Regex>,
+}
+
+impl Selection {
+    /// the Kuiper prototype launch
+    pub fn kuiper_protosats() -> Self {
+        Selection {
+            intdes: vec!["2023-154".to_string()],
+            names: vec!["KUIPER*".to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+            && self.intdes.is_empty()
+            && self.norad_ids.is_empty()
+            && self.names.is_empty()
+            && self.name_regexes.is_empty()
+    }
+
+    /// CelesTrak GP queries (parameter, value) needed to fetch the candidate set
+    pub fn celestrak_queries(&self) -> Vec<(String, String)> {
+        let mut queries: Vec<(String, String)> = self
+            .groups
+            .iter()
+            .map(|group| ("GROUP".to_string(), group.clone()))
+            .chain(
+                self.intdes
+                    .iter()
+                    .map(|intdes| ("INTDES".to_string(), intdes.clone())),
+            )
+            .chain(
+                self.norad_ids
+                    .iter()
+                    .map(|id| ("CATNR".to_string(), id.to_string())),
+            )
+            .collect();
+        if queries.is_empty() {
+            // only name patterns given, so they have to be matched against everything
+            queries.push(("GROUP".to_string(), "active".to_string()));
+        }
+        queries
+    }
+
+    pub fn matches(&self, elements: &Elements) -> bool {
+        self.matches_catalog(elements) && self.matches_name(elements)
+    }
+
+    fn matches_catalog(&self, elements: &Elements) -> bool {
+        // group membership can't be checked locally, so trust whatever the group query returned
+        if !self.groups.is_empty() || (self.intdes.is_empty() && self.norad_ids.is_empty()) {
+            return true;
+        }
+        self.norad_ids.contains(&elements.norad_id)
+            || elements
+                .international_designator
+                .as_ref()
+                .is_some_and(|designator| {
+                    self.intdes
+                        .iter()
+                        .any(|intdes| designator.starts_with(intdes.as_str()))
+                })
+    }
+
+    fn matches_name(&self, elements: &Elements) -> bool {
+        if self.names.is_empty() && self.name_regexes.is_empty() {
+            return true;
+        }
+        elements.object_name.as_ref().is_some_and(|name| {
+            self.names
+                .iter()
+                .any(|pattern| glob_match(&pattern.to_uppercase(), &name.to_uppercase()))
+                || self.name_regexes.iter().any(|regex| regex.is_match(name))
+        })
+    }
+
+    /// Short label for the crowded map and sky plot only; tables, printed output and
+    /// exports use [`full_name`].
+    /// Strips the literal part of the first matching name glob ("KUIPER-P1" → "P1" for
+    /// "KUIPER*") and falls back to the full name or the NORAD ID, so it never fails.
+    pub fn map_label(&self, elements: &Elements) -> String {
+        let Some(name) = elements.object_name.as_deref().map(str::trim) else {
+            return elements.norad_id.to_string();
+        };
+        let upper = name.to_uppercase();
+        self.names
+            .iter()
+            .map(|pattern| pattern.to_uppercase())
+            .find(|pattern| glob_match(pattern, &upper))
+            .and_then(|pattern| {
+                let prefix_len = pattern.find(['*', '?', '[']).unwrap_or(pattern.len());
+                name.get(prefix_len..)
+            })
+            .map(|rest| rest.trim_start_matches(['-', '_', ' ']))
+            .filter(|rest| !rest.is_empty())
+            .unwrap_or(name)
+            .to_string()
+    }
+}
+
+/// The object name, or the NORAD ID for element sets without one
+pub fn full_name(elements: &Elements) -> String {
+    elements
+        .object_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map_or_else(|| elements.norad_id.to_string(), str::to_string)
+}
+
+/// Incremental search in the TUI: whether `query` appears, ignoring case, in the object
+/// name, the NORAD ID or the international designator. An empty query matches everything.
+pub fn search_match(query: &str, elements: &Elements) -> bool {
+    let query = query.trim().to_uppercase();
+    query.is_empty()
+        || elements.norad_id.to_string().contains(&query)
+        || [&elements.object_name, &elements.international_designator]
+            .into_iter()
+            .flatten()
+            .any(|text| text.to_uppercase().contains(&query))
+}
+
+/// Shell-style glob: `*` matches any run of characters, `?` any single character and
+/// `[abc]` / `[a-z]` any character in the set
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_from(&pattern, &text)
+}
+
+fn glob_match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len()).any(|skip| glob_match_from(&pattern[1..], &text[skip..])),
+        Some('?') => !text.is_empty() && glob_match_from(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let Some(close) = pattern.iter().position(|&c| c == ']') else {
+                return text.first() == Some(&'[') && glob_match_from(&pattern[1..], &text[1..]);
+            };
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            let set = &pattern[1..close];
+            let in_set = set.iter().enumerate().any(|(i, &start)| {
+                if set.get(i + 1) == Some(&'-') && i + 2 < set.len() {
+                    (start..=set[i + 2]).contains(&c)
+                } else {
+                    start == c
+                }
+            });
+            in_set && glob_match_from(&pattern[close + 1..], &text[1..])
+        }
+        Some(&literal) => {
+            text.first() == Some(&literal) && glob_match_from(&pattern[1..], &text[1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::RegexBuilder;
+
+    fn elements(name: Option<&str>) -> Elements {
+        Elements::from_tle(
+            name.map(str::to_string),
+            b"1 58012U 23154A   26288.50000000  .00001000  00000-0  50000-4 0  9992",
+            b"2 58012  51.9000 120.0000 0001000  90.0000 270.0000 15.20000000 10004",
+        )
+        .unwrap()
+    }
+
+    fn names(patterns: &[&str]) -> Selection {
+        Selection {
+            names: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("KUIPER-*", "KUIPER-P1"));
+        assert!(glob_match("KUIPER-*", "KUIPER-"));
+        assert!(glob_match("*P1", "KUIPER-P1"));
+        assert!(!glob_match("KUIPER-*", "KUIPER"));
+        assert!(glob_match("KUIPER-P?", "KUIPER-P2"));
+        assert!(!glob_match("KUIPER-P?", "KUIPER-P12"));
+        assert!(glob_match("KUIPER-P[12]", "KUIPER-P2"));
+        assert!(glob_match("KUIPER-[0-9]*", "KUIPER-00012"));
+        assert!(!glob_match("KUIPER-P[12]", "KUIPER-P3"));
+        // globs themselves are case sensitive, the selection uppercases both sides
+        assert!(!glob_match("kuiper*", "KUIPER-P1"));
+        assert!(names(&["kuiper*"]).matches(&elements(Some("Kuiper-P1"))));
+        // the empty pattern only matches an empty name
+        assert!(glob_match("", ""));
+        assert!(!names(&[""]).matches(&elements(Some("KUIPER-P1"))));
+    }
+
+    #[test]
+    fn catalog_and_names() {
+        let kuiper = elements(Some("KUIPER-P1"));
+        assert_eq!(
+            kuiper.international_designator.as_deref(),
+            Some("2023-154A")
+        );
+        assert!(Selection::default().matches(&kuiper));
+        assert!(Selection::kuiper_protosats().matches(&kuiper));
+        // designators match as prefixes, the whole launch or one object
+        for (intdes, matches) in [
+            ("2023-154", true),
+            ("2023-154A", true),
+            ("2023-154B", false),
+            ("2024-154", false),
+        ] {
+            let selection = Selection {
+                intdes: vec![intdes.to_string()],
+                ..Default::default()
+            };
+            assert_eq!(selection.matches(&kuiper), matches, "{}", intdes);
+        }
+        let by_id = Selection {
+            norad_ids: vec![58012],
+            ..Default::default()
+        };
+        assert!(by_id.matches(&kuiper));
+        // name patterns narrow the catalog down, and need a name
+        let narrowed = Selection {
+            names: vec!["STARLINK*".to_string()],
+            ..by_id
+        };
+        assert!(!narrowed.matches(&kuiper));
+        assert!(!names(&["*"]).matches(&elements(None)));
+
+        let regex = RegexBuilder::new(r"^kuiper-p\d$")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let selection = Selection {
+            name_regexes: vec![regex],
+            ..Default::default()
+        };
+        assert!(selection.matches(&kuiper));
+        assert!(!selection.matches(&elements(Some("KUIPER-00012"))));
+    }
+
+    #[test]
+    fn labels() {
+        let selection = names(&["KUIPER*"]);
+        assert_eq!(selection.map_label(&elements(Some("KUIPER-P1"))), "P1");
+        assert_eq!(selection.map_label(&elements(Some("KUIPER"))), "KUIPER");
+        assert_eq!(
+            selection.map_label(&elements(Some("ISS (ZARYA)"))),
+            "ISS (ZARYA)"
+        );
+        assert_eq!(selection.map_label(&elements(None)), "58012");
+        assert_eq!(full_name(&elements(Some(" KUIPER-P1 "))), "KUIPER-P1");
+        assert_eq!(full_name(&elements(Some(""))), "58012");
+    }
+
+    #[test]
+    fn search() {
+        let kuiper = elements(Some("KUIPER-P1"));
+        // name, NORAD ID and designator, in part and ignoring case
+        assert!(search_match("p1", &kuiper));
+        assert!(search_match("Kuiper", &kuiper));
+        assert!(search_match("5801", &kuiper));
+        assert!(search_match("2023-154a", &kuiper));
+        assert!(!search_match("starlink", &kuiper));
+        assert!(!search_match("25544", &kuiper));
+        // an empty or blank query matches everything, surrounding blanks are ignored
+        assert!(search_match("", &kuiper));
+        assert!(search_match("  ", &kuiper));
+        assert!(search_match(" p1 ", &kuiper));
+        // without a name the other fields still match
+        assert!(search_match("58012", &elements(None)));
+        assert!(!search_match("KUIPER", &elements(None)));
+    }
+}
@@ -0,0 +1,306 @@
+use anyhow::{bail, Context};
+use sgp4::Elements;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where the orbital elements come from
+#[derive(Clone)]
+pub enum ElementSource {
+    /// Live CelesTrak GP API queries as (parameter, value) pairs, merged together
+    Celestrak(Vec<(String, String)>),
+    /// A CelesTrak-style OMM JSON array
+    OmmFile(PathBuf),
+    /// A two-line or three-line TLE text file
+    TleFile(PathBuf),
+    /// A directory of OMM and/or TLE files
+    Directory(PathBuf),
+}
+
+/// Elements from a source, along with the files of a directory that had to be left out
+pub struct Loaded {
+    pub elements: Vec<Elements>,
+    /// the error for each unreadable or malformed file
+    pub skipped: Vec<String>,
+}
+
+impl Loaded {
+    fn complete(elements: Vec<Elements>) -> Self {
+        Loaded {
+            elements,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// ", skipped ..." for the end of a status line, empty if every file loaded
+    pub fn skipped_note(&self) -> String {
+        skipped_note(&self.skipped)
+    }
+}
+
+/// ", skipped ..." for the end of a status line, empty if nothing was skipped
+pub fn skipped_note(skipped: &[String]) -> String {
+    match skipped.len() {
+        0 => String::new(),
+        1 => format!(", skipped {}", skipped[0]),
+        n => format!(", skipped {} files: {}", n, skipped.join("; ")),
+    }
+}
+
+impl ElementSource {
+    /// picks a source for a path, guessing the format from the file extension
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if path.is_dir() {
+            ElementSource::Directory(path)
+        } else if is_json(&path) {
+            ElementSource::OmmFile(path)
+        } else {
+            ElementSource::TleFile(path)
+        }
+    }
+
+    /// short description for the loading screen
+    pub fn describe(&self) -> String {
+        match self {
+            ElementSource::Celestrak(_) => "celestrak".to_string(),
+            ElementSource::OmmFile(path)
+            | ElementSource::TleFile(path)
+            | ElementSource::Directory(path) => path.display().to_string(),
+        }
+    }
+
+    /// file name stem for caching this source, `None` for local sources
+    pub fn cache_key(&self) -> Option<String> {
+        let ElementSource::Celestrak(queries) = self else {
+            return None;
+        };
+        let key = queries
+            .iter()
+            .map(|(param, value)| format!("{}-{}", param, value))
+            .collect::<Vec<String>>()
+            .join("_")
+            .to_lowercase()
+            .replace(
+                |c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_',
+                "",
+            );
+        Some(format!("celestrak_{}", key))
+    }
+
+    pub fn load(&self) -> anyhow::Result<Loaded> {
+        match self {
+            ElementSource::Celestrak(queries) => fetch_celestrak(queries).map(Loaded::complete),
+            ElementSource::OmmFile(path) => load_omm_file(path).map(Loaded::complete),
+            ElementSource::TleFile(path) => load_tle_file(path).map(Loaded::complete),
+            ElementSource::Directory(path) => load_directory(path),
+        }
+    }
+}
+
+fn fetch_celestrak(queries: &[(String, String)]) -> anyhow::Result<Vec<Elements>> {
+    let mut merged: Vec<Elements> = Vec::new();
+    for (param, value) in queries {
+        let body = ureq::get("https://celestrak.com/NORAD/elements/gp.php")
+            .query(param, value)
+            .query("FORMAT", "json")
+            .call()
+            .with_context(|| format!("querying celestrak for {}={}", param, value))?
+            .into_string()?;
+        // celestrak answers "No GP data found" in plain text rather than an empty array
+        if !body.trim_start().starts_with('[') {
+            continue;
+        }
+        let elements_vec: Vec<Elements> = serde_json::from_str(&body)
+            .with_context(|| format!("parsing celestrak response for {}={}", param, value))?;
+        for elements in elements_vec {
+            if !merged.iter().any(|e| e.norad_id == elements.norad_id) {
+                merged.push(elements);
+            }
+        }
+    }
+    Ok(merged)
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn is_tle(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ["tle", "txt", "2le", "3le"]
+            .iter()
+            .any(|known| ext.eq_ignore_ascii_case(known))
+    })
+}
+
+fn load_omm_file(path: &Path) -> anyhow::Result<Vec<Elements>> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("parsing OMM JSON in {}", path.display()))
+}
+
+fn load_tle_file(path: &Path) -> anyhow::Result<Vec<Elements>> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    parse_tle_text(&text).with_context(|| format!("parsing TLEs in {}", path.display()))
+}
+
+/// Loads every OMM and TLE file in a directory, keeping the newest element set per NORAD ID.
+/// A file that can't be read or parsed is skipped, so the rest still load.
+fn load_directory(path: &Path) -> anyhow::Result<Loaded> {
+    let mut files = fs::read_dir(path)
+        .with_context(|| format!("reading directory {}", path.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    files.sort();
+
+    let mut newest: HashMap<u64, Elements> = HashMap::new();
+    let mut skipped = Vec::new();
+    for file in files {
+        let loaded = if is_json(&file) {
+            load_omm_file(&file)
+        } else if is_tle(&file) {
+            load_tle_file(&file)
+        } else {
+            continue;
+        };
+        let elements_vec = match loaded {
+            Ok(elements_vec) => elements_vec,
+            Err(error) => {
+                skipped.push(format!("{:#}", error));
+                continue;
+            }
+        };
+        for elements in elements_vec {
+            let keep = newest
+                .get(&elements.norad_id)
+                .is_none_or(|existing| existing.datetime < elements.datetime);
+            if keep {
+                newest.insert(elements.norad_id, elements);
+            }
+        }
+    }
+
+    let mut elements_vec: Vec<Elements> = newest.into_values().collect();
+    elements_vec.sort_by_key(|elements| elements.norad_id);
+    Ok(Loaded {
+        elements: elements_vec,
+        skipped,
+    })
+}
+
+/// Parses two-line and three-line TLE text, which may be mixed in one file.
+/// Blank lines are ignored and a leading "0 " on name lines (space-track style) is stripped.
+pub fn parse_tle_text(text: &str) -> anyhow::Result<Vec<Elements>> {
+    // numbered before blank lines are dropped, so errors point at the right line
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .map(str::trim_end)
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+
+    let mut elements_vec = Vec::new();
+    let mut name: Option<&str> = None;
+    let mut index = 0;
+    while index < lines.len() {
+        let (number, line) = lines[index];
+        if line.starts_with("1 ") {
+            let Some((_, line2)) = lines.get(index + 1).filter(|(_, l)| l.starts_with("2 ")) else {
+                bail!("line {} has no matching TLE line 2", number + 1);
+            };
+            let object_name = name
+                .take()
+                .map(|n| n.strip_prefix("0 ").unwrap_or(n).trim().to_string());
+            elements_vec.push(
+                Elements::from_tle(object_name, line.as_bytes(), line2.as_bytes())
+                    .map_err(|e| anyhow::anyhow!("{}", e))?,
+            );
+            index += 2;
+        } else {
+            name = Some(line);
+            index += 1;
+        }
+    }
+    Ok(elements_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS_1: &str = "1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992";
+    const ISS_2: &str = "2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008";
+    /// the same orbit with a later epoch
+    const ISS_NEWER_1: &str =
+        "1 25544U 98067A   20195.50000000 -.00002218  00000-0 -31515-4 0  9996";
+    const KUIPER_1: &str = "1 58012U 23154A   26288.50000000  .00001000  00000-0  50000-4 0  9992";
+    const KUIPER_2: &str = "2 58012  51.9000 120.0000 0001000  90.0000 270.0000 15.20000000 10004";
+
+    #[test]
+    fn two_and_three_line_sets() {
+        let text = format!(
+            "{}\n{}\n\n0 KUIPER-P1\n{}\n{}\n",
+            ISS_1, ISS_2, KUIPER_1, KUIPER_2
+        );
+        let elements_vec = parse_tle_text(&text).unwrap();
+        assert_eq!(elements_vec.len(), 2);
+        assert_eq!(elements_vec[0].norad_id, 25544);
+        assert_eq!(elements_vec[0].object_name, None);
+        assert_eq!(elements_vec[1].norad_id, 58012);
+        assert_eq!(elements_vec[1].object_name.as_deref(), Some("KUIPER-P1"));
+
+        let elements_vec = parse_tle_text(&format!("ISS (ZARYA)  \n{}\n{}", ISS_1, ISS_2)).unwrap();
+        assert_eq!(elements_vec[0].object_name.as_deref(), Some("ISS (ZARYA)"));
+    }
+
+    #[test]
+    fn missing_line_2() {
+        let text = format!(
+            "\n\nISS\n{}\n\nKUIPER-P1\n{}\n{}\n",
+            ISS_1, KUIPER_1, KUIPER_2
+        );
+        let error = parse_tle_text(&text).err().unwrap();
+        assert_eq!(error.to_string(), "line 4 has no matching TLE line 2");
+        assert!(parse_tle_text(ISS_1).is_err());
+    }
+
+    #[test]
+    fn newest_per_norad_id_in_directory() {
+        let dir = std::env::temp_dir().join(format!("tuiper-source-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, lines: &[&str]| fs::write(dir.join(name), lines.join("\n"));
+        // the newer ISS set sorts first, so the older one must not replace it
+        write("a.tle", &[ISS_NEWER_1, ISS_2]).unwrap();
+        write("b.txt", &["ISS", ISS_1, ISS_2, KUIPER_1, KUIPER_2]).unwrap();
+        write("notes.md", &["not elements"]).unwrap();
+        // a broken file is left out rather than losing the whole directory
+        write("c.json", &["[{\"OBJECT_NAME\": "]).unwrap();
+        write("d.tle", &[ISS_1]).unwrap();
+
+        let loaded = load_directory(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let Loaded {
+            elements: elements_vec,
+            skipped,
+        } = loaded.unwrap();
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped[0].starts_with("parsing OMM JSON in ") && skipped[0].contains("c.json"));
+        assert!(skipped[1].starts_with("parsing TLEs in ") && skipped[1].contains("d.tle"));
+        assert_eq!(
+            skipped_note(&skipped[..1]),
+            format!(", skipped {}", skipped[0])
+        );
+        assert!(skipped_note(&skipped).starts_with(", skipped 2 files: parsing OMM JSON"));
+        assert_eq!(skipped_note(&[]), "");
+        let norad_ids: Vec<u64> = elements_vec.iter().map(|e| e.norad_id).collect();
+        assert_eq!(norad_ids, [25544, 58012]);
+        assert_eq!(elements_vec[0].object_name, None);
+        assert_eq!(
+            elements_vec[0].datetime,
+            parse_tle_text(&format!("{}\n{}", ISS_NEWER_1, ISS_2)).unwrap()[0].datetime
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::frames::{teme_to_ecef, EarthOrientation, GroundPos};
+use hifitime::prelude::*;
+
+/// astronomical unit in km
+pub const AU: f64 = 149597870.7;
+
+/// Sun elevations (degrees) where civil, nautical and astronomical twilight end
+pub const TWILIGHT: [f64; 3] = [-6.0, -12.0, -18.0];
+
+/// Geocentric sun position in km, good to about 0.01° (Vallado, Algorithm 29).
+/// The result is in the mean-of-date frame, which is within arcseconds of TEME.
+pub fn sun_position(time: Epoch) -> [f64; 3] {
+    let t = (time.to_jde_tt_days() - 2451545.0) / 36525.0;
+    let mean_longitude = 280.460 + 36000.771 * t;
+    let mean_anomaly = (357.5291092 + 35999.05034 * t).to_radians();
+    let ecliptic_longitude = (mean_longitude
+        + 1.914666471 * mean_anomaly.sin()
+        + 0.019994643 * (2.0 * mean_anomaly).sin())
+    .to_radians();
+    let obliquity = (23.439291 - 0.0130042 * t).to_radians();
+    let distance =
+        (1.000140612 - 0.016708617 * mean_anomaly.cos() - 0.000139589 * (2.0 * mean_anomaly).cos())
+            * AU;
+    [
+        distance * ecliptic_longitude.cos(),
+        distance * obliquity.cos() * ecliptic_longitude.sin(),
+        distance * obliquity.sin() * ecliptic_longitude.sin(),
+    ]
+}
+
+/// the point on the earth with the sun directly overhead (geocentric latitude)
+pub fn subsolar_point(time: Epoch, eop: &EarthOrientation) -> GroundPos {
+    let r = teme_to_ecef(sun_position(time), time, eop);
+    GroundPos {
+        lat: r[2].atan2(r[0].hypot(r[1])).to_degrees(),
+        lon: r[1].atan2(r[0]).to_degrees(),
+        alt: 0.0,
+    }
+}
+
+/// sun elevation in degrees at a point, ignoring refraction and parallax
+pub fn sun_elevation(subsolar: &GroundPos, lat: f64, lon: f64) -> f64 {
+    let (sin_dec, cos_dec) = subsolar.lat.to_radians().sin_cos();
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    let hour_angle = (lon - subsolar.lon).to_radians();
+    (sin_lat * sin_dec + cos_lat * cos_dec * hour_angle.cos())
+        .clamp(-1.0, 1.0)
+        .asin()
+        .to_degrees()
+}
+
+/// (lon, lat) points every degree of longitude where the sun is at the horizon
+pub fn terminator(subsolar: &GroundPos) -> Vec<(f64, f64)> {
+    let declination = subsolar.lat.to_radians();
+    (-180..=180)
+        .map(|lon| {
+            let hour_angle = (lon as f64 - subsolar.lon).to_radians();
+            // sin(lat) sin(dec) + cos(lat) cos(dec) cos(H) = 0
+            let lat = (-declination.cos() * hour_angle.cos() / declination.sin()).atan();
+            (lon as f64, lat.to_degrees())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn sun_vallado_example_5_1() {
+        let time = Epoch::from_str("2006-04-02T00:00:00 UTC").unwrap();
+        let r = sun_position(time).map(|c| c / AU);
+        let expected = [0.9771945, 0.1924583, 0.0834171];
+        for (actual, expected) in r.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-4, "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn subsolar_point_near_equinox_noon() {
+        // close to the March 2024 equinox, around local noon at Greenwich
+        let time = Epoch::from_str("2024-03-20T12:07:00 UTC").unwrap();
+        let point = subsolar_point(time, &EarthOrientation::default());
+        assert!(point.lat.abs() < 0.5, "{:?}", point);
+        assert!(point.lon.abs() < 1.0, "{:?}", point);
+        assert!((sun_elevation(&point, point.lat, point.lon) - 90.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,152 @@
+//! `--tracks`: the ground tracks drawn on the map as KML or GeoJSON, for Google Earth or GIS
+
+use crate::{
+    app::{TrackPoint, Tracked},
+    clock,
+    projection::antimeridian_crossing,
+    selection::full_name,
+    xml,
+};
+use serde_json::json;
+
+/// How `--tracks` writes, picked by the file extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackFormat {
+    Kml,
+    GeoJson,
+}
+
+impl TrackFormat {
+    /// ".kml", or ".geojson" / ".json"
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "kml" => Some(TrackFormat::Kml),
+            "geojson" | "json" => Some(TrackFormat::GeoJson),
+            _ => None,
+        }
+    }
+}
+
+/// [lon, lat] lines of a track, broken where it crosses the antimeridian so that no line
+/// runs the long way round the earth (RFC 7946, section 3.1.9)
+pub fn split_antimeridian(track: &[TrackPoint]) -> Vec<Vec<[f64; 2]>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    for (i, point) in track.iter().enumerate() {
+        let here = (point.pos.lat, point.pos.lon);
+        if let Some(previous) = i.checked_sub(1).map(|i| &track[i]) {
+            let before = (previous.pos.lat, previous.pos.lon);
+            if let Some((lat, edge)) = antimeridian_crossing(before, here) {
+                line.push([edge, lat]);
+                lines.push(std::mem::take(&mut line));
+                line.push([-edge, lat]);
+            }
+        }
+        line.push([here.1, here.0]);
+    }
+    if line.len() > 1 {
+        lines.push(line);
+    }
+    lines
+}
+
+/// a KML document with a time stamped track per satellite
+pub fn kml(tracked: &[Tracked]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <kml xmlns=\"http://www.opengis.net/kml/2.2\" \
+         xmlns:gx=\"http://www.google.com/kml/ext/2.2\">\n\
+         \x20 <Document>\n\
+         \x20   <name>tuiper ground tracks</name>\n",
+    );
+    for tracked in tracked {
+        let elements = &tracked.sat.elements;
+        out.push_str(&format!(
+            "    <Placemark>\n      <name>{}</name>\n      <description>NORAD {}</description>\n",
+            xml::escape(&full_name(elements)),
+            elements.norad_id
+        ));
+        out.push_str("      <gx:Track>\n        <altitudeMode>clampToGround</altitudeMode>\n");
+        for point in &tracked.track {
+            out.push_str(&format!(
+                "        <when>{}</when>\n",
+                clock::format_iso(point.time)
+            ));
+        }
+        for point in &tracked.track {
+            out.push_str(&format!(
+                "        <gx:coord>{:.6} {:.6} 0</gx:coord>\n",
+                point.pos.lon, point.pos.lat
+            ));
+        }
+        out.push_str("      </gx:Track>\n    </Placemark>\n");
+    }
+    out.push_str("  </Document>\n</kml>\n");
+    out
+}
+
+/// a GeoJSON feature collection with a MultiLineString per satellite
+pub fn geojson(tracked: &[Tracked]) -> anyhow::Result<String> {
+    let features: Vec<_> = tracked
+        .iter()
+        .map(|tracked| {
+            let elements = &tracked.sat.elements;
+            json!({
+                "type": "Feature",
+                "properties": {
+                    "name": full_name(elements),
+                    "norad_id": elements.norad_id,
+                    "start": tracked.track.first().map(|point| clock::format_iso(point.time)),
+                    "stop": tracked.track.last().map(|point| clock::format_iso(point.time)),
+                },
+                "geometry": {
+                    "type": "MultiLineString",
+                    "coordinates": split_antimeridian(&tracked.track),
+                },
+            })
+        })
+        .collect();
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    Ok(serde_json::to_string_pretty(&collection)? + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eclipse::Illumination, frames::GroundPos};
+    use hifitime::Epoch;
+
+    fn point(lat: f64, lon: f64) -> TrackPoint {
+        TrackPoint {
+            time: Epoch::from_gregorian_utc_at_midnight(2024, 5, 1),
+            pos: GroundPos {
+                lat,
+                lon,
+                alt: 600.0,
+            },
+            light: Illumination::Sunlit,
+        }
+    }
+
+    #[test]
+    fn splits_at_antimeridian() {
+        let track = [
+            point(0.0, 170.0),
+            point(2.0, 179.0),
+            point(4.0, -179.0),
+            point(6.0, -170.0),
+        ];
+        assert_eq!(
+            split_antimeridian(&track),
+            vec![
+                vec![[170.0, 0.0], [179.0, 2.0], [180.0, 3.0]],
+                vec![[-180.0, 3.0], [-179.0, 4.0], [-170.0, 6.0]],
+            ]
+        );
+        assert_eq!(split_antimeridian(&track[..2]).len(), 1);
+        assert!(split_antimeridian(&track[..1]).is_empty());
+    }
+}
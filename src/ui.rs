@@ -0,0 +1,700 @@
+use crate::{
+    app::{App, Prompt, Snapshot, Tracked, View},
+    clock::{format_span, format_utc, format_utc_time},
+    eclipse::Illumination,
+    footprint::footprint,
+    frames::GroundPos,
+    observer::LookAngles,
+    passes::pass_track,
+    projection::Projection,
+    satellite::Satellite,
+    selection::full_name,
+    sun::{sun_elevation, terminator, TWILIGHT},
+};
+use hifitime::prelude::*;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line as TextLine,
+    widgets::{
+        canvas::{Canvas, Circle, Context, Line, Points},
+        Block, Borders, List, ListItem, Paragraph, Row, Table, TableState,
+    },
+    Frame,
+};
+use std::collections::HashMap;
+
+/// width of the side panels next to the map
+const SIDE_WIDTH: u16 = 40;
+/// most rows the pass table takes up under the map
+const PASS_ROWS: u16 = 10;
+/// most rows the satellite table takes up under the map
+const LIST_ROWS: u16 = 8;
+/// height of the selected satellite's details, borders included
+const DETAILS_HEIGHT: u16 = 19;
+
+/// draws a frame and returns where the map or sky plot went
+pub fn draw(frame: &mut Frame, app: &App, snapshot: &Snapshot) -> Rect {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(frame.size());
+
+    let show_observer = app.observer.is_some();
+    let show_lost = !snapshot.lost.is_empty();
+    let show_eclipses = !app.eclipses.is_empty();
+    let selected = app
+        .satellites
+        .iter()
+        .find(|sat| Some(sat.elements.norad_id) == app.selected);
+    let side_width = if show_observer || show_lost || show_eclipses || selected.is_some() {
+        SIDE_WIDTH
+    } else {
+        0
+    };
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(side_width)])
+        .split(layout[0]);
+
+    let upcoming = app.upcoming_passes(snapshot.time, &snapshot.shown).count() as u16;
+    let pass_height = if app.observer.is_some() && app.show_passes {
+        upcoming.min(PASS_ROWS) + 3
+    } else {
+        0
+    };
+    let shown = snapshot.shown.len() as u16;
+    let list_height = if app.show_list && (shown > 0 || !app.search.is_empty()) {
+        shown.min(LIST_ROWS) + 3
+    } else {
+        0
+    };
+    let map_column = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(list_height),
+            Constraint::Length(pass_height),
+        ])
+        .split(columns[0]);
+
+    match app.view {
+        View::Map => draw_map(frame, app, snapshot, map_column[0]),
+        View::SkyPlot => draw_sky_plot(frame, app, snapshot, map_column[0]),
+    }
+    if list_height > 0 {
+        draw_satellites(frame, app, snapshot, map_column[1]);
+    }
+    if pass_height > 0 {
+        draw_passes(frame, app, snapshot, map_column[2]);
+    }
+
+    let side = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if selected.is_some() {
+                DETAILS_HEIGHT
+            } else {
+                0
+            }),
+            Constraint::Min(if show_observer { 5 } else { 0 }),
+            Constraint::Min(if show_eclipses { 5 } else { 0 }),
+            Constraint::Length(if show_lost {
+                snapshot.lost.len() as u16 + 2
+            } else {
+                0
+            }),
+        ])
+        .split(columns[1]);
+    if let Some(sat) = selected {
+        draw_details(frame, snapshot, sat, side[0]);
+    }
+    if show_observer {
+        draw_observer(frame, app, snapshot, side[1]);
+    }
+    if show_eclipses {
+        draw_eclipses(frame, app, snapshot, side[2]);
+    }
+    if show_lost {
+        draw_lost(frame, snapshot, side[3]);
+    }
+
+    let status = match &app.prompt {
+        Some(Prompt::JumpTo(text)) => format!("jump to UTC (YYYY-MM-DD [HH:MM[:SS]]): {}_", text),
+        Some(Prompt::Search) => format!(
+            "/{}_  (name, NORAD ID or designator; enter to keep, esc to clear)",
+            app.search
+        ),
+        None => match snapshot.epoch_notice() {
+            Some(notice) if !app.status.is_empty() => format!("{}; {}", notice, app.status),
+            Some(notice) => notice,
+            None => app.status.clone(),
+        },
+    };
+    frame.render_widget(Paragraph::new(status), layout[1]);
+    map_column[0]
+}
+
+fn draw_map(frame: &mut Frame, app: &App, snapshot: &Snapshot, area: Rect) {
+    let (x_bounds, y_bounds) = app.viewport.bounds();
+    frame.render_widget(
+        Canvas::default()
+            .block(
+                Block::default()
+                    .title(app.title(snapshot.time))
+                    .borders(Borders::ALL),
+            )
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .paint(|ctx| {
+                let projection = app.projection;
+                if app.show_night {
+                    draw_night(ctx, projection, &snapshot.subsolar);
+                    ctx.layer();
+                }
+                if projection.is_round() {
+                    ctx.draw(&Circle {
+                        x: 0.0,
+                        y: 0.0,
+                        radius: projection.radius(),
+                        color: Color::DarkGray,
+                    });
+                }
+                ctx.draw(&Points {
+                    coords: &projection.coastline(app.viewport.resolution()),
+                    color: Color::White,
+                });
+                ctx.layer();
+                if app.show_night {
+                    print_at(ctx, projection, &snapshot.subsolar, "☀".yellow());
+                    ctx.layer();
+                }
+                if let Some(observer) = &app.observer {
+                    print_at(ctx, projection, &observer.location, "📡");
+                    ctx.layer();
+                }
+                if app.show_footprints {
+                    for tracked in &snapshot.tracked {
+                        let outline = footprint(&tracked.position, app.args.footprint_elevation);
+                        draw_path(ctx, projection, &outline, Color::Green);
+                    }
+                    ctx.layer();
+                }
+                snapshot.tracked.iter().for_each(|tracked| {
+                    let past: Vec<(f64, f64)> = tracked
+                        .past
+                        .iter()
+                        .map(|point| (point.pos.lat, point.pos.lon))
+                        .collect();
+                    draw_path(ctx, projection, &past, Color::DarkGray);
+                    // each step takes the color of the light at its start
+                    for pair in tracked.track.windows(2) {
+                        let (from, to) = (pair[0].pos, pair[1].pos);
+                        draw_path(
+                            ctx,
+                            projection,
+                            &[(from.lat, from.lon), (to.lat, to.lon)],
+                            light_color(pair[0].light),
+                        );
+                    }
+                    let label =
+                        format!("🛰️{}", app.args.selection.map_label(&tracked.sat.elements))
+                            .fg(light_color(tracked.light));
+                    if app.selected == Some(tracked.sat.elements.norad_id) {
+                        print_at(ctx, projection, &tracked.position, label.reversed());
+                    } else {
+                        print_at(ctx, projection, &tracked.position, label);
+                    }
+                    ctx.layer();
+                });
+            }),
+        area,
+    );
+}
+
+/// joins (lat, lon) points with lines, broken wherever the projection can't show a step
+fn draw_path(ctx: &mut Context, projection: Projection, points: &[(f64, f64)], color: Color) {
+    for pair in points.windows(2) {
+        for (x1, y1, x2, y2) in projection.segments(pair[0], pair[1]) {
+            ctx.draw(&Line::new(x1, y1, x2, y2, color));
+        }
+    }
+}
+
+/// prints `text` at a ground position, if the projection shows it
+fn print_at<'a>(
+    ctx: &mut Context<'a>,
+    projection: Projection,
+    pos: &GroundPos,
+    text: impl Into<TextLine<'a>>,
+) {
+    if let Some((x, y)) = projection.project(pos.lat, pos.lon) {
+        ctx.print(x, y, text);
+    }
+}
+
+/// red in sunlight, yellow in the penumbra and blue in the earth's shadow
+fn light_color(light: Illumination) -> Color {
+    match light {
+        Illumination::Sunlit => Color::Red,
+        Illumination::Penumbra => Color::Yellow,
+        Illumination::Umbra => Color::Blue,
+    }
+}
+
+/// shades twilight and night with dots, darker the lower the sun, and draws the terminator
+fn draw_night(ctx: &mut Context, projection: Projection, subsolar: &GroundPos) {
+    // civil, nautical, astronomical twilight and full night
+    let mut bands: [Vec<(f64, f64)>; 4] = Default::default();
+    for lat in (-89..=89).step_by(2) {
+        for lon in (-179..=179).step_by(2) {
+            let (lat, lon) = (lat as f64, lon as f64);
+            let elevation = sun_elevation(subsolar, lat, lon);
+            if elevation >= 0.0 {
+                continue;
+            }
+            let band = TWILIGHT
+                .iter()
+                .position(|&limit| elevation >= limit)
+                .unwrap_or(TWILIGHT.len());
+            bands[band].extend(projection.project(lat, lon));
+        }
+    }
+    let colors = [
+        Color::Indexed(25),
+        Color::Indexed(19),
+        Color::Indexed(18),
+        Color::Indexed(17),
+    ];
+    for (coords, color) in bands.iter().zip(colors) {
+        ctx.draw(&Points { coords, color });
+    }
+    let terminator: Vec<(f64, f64)> = terminator(subsolar)
+        .into_iter()
+        .map(|(lon, lat)| (lat, lon))
+        .collect();
+    draw_path(ctx, projection, &terminator, Color::Yellow);
+}
+
+/// canvas position of a direction in the sky: zenith in the middle, horizon on the unit circle
+fn sky_xy(look: &LookAngles) -> (f64, f64) {
+    let radius = (90.0 - look.elevation.max(0.0)) / 90.0;
+    let azimuth = look.azimuth.to_radians();
+    (radius * azimuth.sin(), radius * azimuth.cos())
+}
+
+/// polar azimuth / elevation plot of the selected satellite's current or next pass
+fn draw_sky_plot(frame: &mut Frame, app: &App, snapshot: &Snapshot, area: Rect) {
+    let Some(observer) = &app.observer else {
+        frame.render_widget(
+            Paragraph::new("the sky plot needs an observer, see --observer")
+                .block(Block::default().title("Sky plot").borders(Borders::ALL)),
+            area,
+        );
+        return;
+    };
+    let pass = app.sky_plot_pass(snapshot.time);
+    let propagator = pass.and_then(|pass| {
+        app.satellites
+            .iter()
+            .find(|sat| sat.elements.norad_id == pass.norad_id)
+            .and_then(|sat| sat.propagator.as_ref().ok())
+    });
+    let track = match (pass, propagator) {
+        (Some(pass), Some(propagator)) => pass_track(
+            propagator,
+            observer,
+            &app.args.earth_orientation,
+            pass,
+            Unit::Second * 10,
+        ),
+        _ => Vec::new(),
+    };
+    let live = pass.and_then(|pass| {
+        snapshot
+            .tracked
+            .iter()
+            .find(|tracked| tracked.sat.elements.norad_id == pass.norad_id)
+            .and_then(|tracked| Some((tracked.look?, tracked)))
+            .filter(|(look, _)| look.elevation >= 0.0)
+    });
+    let title = match pass {
+        Some(pass) => format!(
+            "Sky plot: {} {} - {}, max {:.1}° at {} (v for map, [ ] to pick)",
+            pass.name,
+            format_utc(pass.aos),
+            format_utc_time(pass.los),
+            pass.max_elevation,
+            format_utc_time(pass.tca)
+        ),
+        None => "Sky plot: no upcoming pass (v for map, [ ] to pick)".to_string(),
+    };
+
+    // braille cells are twice as tall as they are wide, so stretch x to keep circles round
+    let inner_width = area.width.saturating_sub(2).max(1) as f64;
+    let inner_height = (area.height.saturating_sub(2).max(1) * 2) as f64;
+    let y_bound = 1.15;
+    let x_bound = y_bound * inner_width / inner_height;
+    frame.render_widget(
+        Canvas::default()
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .x_bounds([-x_bound, x_bound])
+            .y_bounds([-y_bound, y_bound])
+            .paint(|ctx| {
+                for (radius, label) in [(1.0, "0°"), (2.0 / 3.0, "30°"), (1.0 / 3.0, "60°")] {
+                    ctx.draw(&Circle {
+                        x: 0.0,
+                        y: 0.0,
+                        radius,
+                        color: Color::DarkGray,
+                    });
+                    ctx.print(0.02, radius, label.dark_gray());
+                }
+                ctx.draw(&Line::new(-1.0, 0.0, 1.0, 0.0, Color::DarkGray));
+                ctx.draw(&Line::new(0.0, -1.0, 0.0, 1.0, Color::DarkGray));
+                ctx.print(0.0, 1.08, "N");
+                ctx.print(0.0, -1.08, "S");
+                ctx.print(1.04, 0.0, "E");
+                ctx.print(-1.1, 0.0, "W");
+                ctx.layer();
+                for pair in track.windows(2) {
+                    let (x1, y1) = sky_xy(&pair[0]);
+                    let (x2, y2) = sky_xy(&pair[1]);
+                    ctx.draw(&Line::new(x1, y1, x2, y2, Color::Yellow));
+                }
+                if let (Some(first), Some(last)) = (track.first(), track.last()) {
+                    let (x, y) = sky_xy(first);
+                    ctx.print(x, y, "AOS".green());
+                    let (x, y) = sky_xy(last);
+                    ctx.print(x, y, "LOS".red());
+                }
+                ctx.layer();
+                if let Some((look, tracked)) = live {
+                    let (x, y) = sky_xy(&look);
+                    let label = app.args.selection.map_label(&tracked.sat.elements);
+                    ctx.print(x, y, format!("🛰️{}", label));
+                }
+            }),
+        area,
+    );
+}
+
+/// azimuth, elevation and range of every satellite as seen by the observer
+fn draw_observer(frame: &mut Frame, app: &App, snapshot: &Snapshot, area: Rect) {
+    let rows: Vec<Row> = snapshot
+        .tracked
+        .iter()
+        .filter_map(|tracked| {
+            let look = tracked.look?;
+            let row = Row::new(vec![
+                full_name(&tracked.sat.elements),
+                format!("{:.1}", look.azimuth),
+                format!("{:.1}", look.elevation),
+                format!("{:.0}", look.range),
+                format!("{:+.2}", look.range_rate),
+            ]);
+            Some(if look.elevation > 0.0 {
+                row.green()
+            } else {
+                row
+            })
+        })
+        .collect();
+    let title = app.observer.as_ref().map_or(String::new(), |observer| {
+        format!(
+            "Observer {:.3}, {:.3}",
+            observer.location.lat, observer.location.lon
+        )
+    });
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(8),
+                Constraint::Length(6),
+                Constraint::Length(5),
+                Constraint::Length(6),
+                Constraint::Length(6),
+            ],
+        )
+        .header(Row::new(vec!["sat", "az°", "el°", "km", "km/s"]).bold())
+        .block(Block::default().title(title).borders(Borders::ALL)),
+        area,
+    );
+}
+
+/// every tracked satellite with its current position, the selected one highlighted
+fn draw_satellites(frame: &mut Frame, app: &App, snapshot: &Snapshot, area: Rect) {
+    let positions: HashMap<u64, &Tracked> = snapshot
+        .tracked
+        .iter()
+        .map(|tracked| (tracked.sat.elements.norad_id, tracked))
+        .collect();
+    let rows: Vec<Row> = app
+        .shown_satellites()
+        .map(|sat| {
+            let age = sat
+                .element_age(snapshot.time)
+                .map(|days| format!("{:+.1}d", days))
+                .unwrap_or_default();
+            let mut cells = vec![full_name(&sat.elements), sat.elements.norad_id.to_string()];
+            match positions.get(&sat.elements.norad_id) {
+                Some(tracked) => {
+                    cells.extend([
+                        format!("{:.2}", tracked.position.lat),
+                        format!("{:.2}", tracked.position.lon),
+                        format!("{:.1}", tracked.position.alt),
+                        format!("{:.3}", tracked.speed),
+                        age,
+                    ]);
+                    Row::new(cells)
+                }
+                None => {
+                    cells.extend(["-", "-", "-", "-"].map(String::from));
+                    cells.push(age);
+                    Row::new(cells).red()
+                }
+            }
+        })
+        .collect();
+    let shown = rows.len();
+    let mut state = TableState::default().with_selected(app.selected.and_then(|selected| {
+        app.shown_satellites()
+            .position(|sat| sat.elements.norad_id == selected)
+    }));
+    frame.render_stateful_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(8),
+                Constraint::Length(6),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(7),
+                Constraint::Length(6),
+                Constraint::Length(7),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "sat", "norad", "lat°", "lon°", "alt km", "km/s", "age",
+            ])
+            .bold(),
+        )
+        .highlight_style(Style::new().reversed())
+        .block(
+            Block::default()
+                .title(if app.search.is_empty() {
+                    format!(
+                        "Satellites ({}, up/down to select, / to search, t to hide)",
+                        app.satellites.len()
+                    )
+                } else {
+                    format!(
+                        "Satellites ({} of {} matching /{})",
+                        shown,
+                        app.satellites.len(),
+                        app.search
+                    )
+                })
+                .borders(Borders::ALL),
+        ),
+        area,
+        &mut state,
+    );
+}
+
+/// the selected satellite's state and full element set
+fn draw_details(frame: &mut Frame, snapshot: &Snapshot, sat: &Satellite, area: Rect) {
+    let elements = &sat.elements;
+    let tracked = snapshot
+        .tracked
+        .iter()
+        .find(|tracked| tracked.sat.elements.norad_id == elements.norad_id);
+    let mut lines = vec![
+        format!(
+            "name     {}",
+            elements.object_name.as_deref().unwrap_or("-")
+        ),
+        format!(
+            "NORAD ID {}  {}",
+            elements.norad_id,
+            elements.international_designator.as_deref().unwrap_or("")
+        ),
+    ];
+    match tracked {
+        Some(tracked) => lines.extend([
+            format!(
+                "position {:.3}°, {:.3}°",
+                tracked.position.lat, tracked.position.lon
+            ),
+            format!(
+                "altitude {:.1} km, {:.3} km/s",
+                tracked.position.alt, tracked.speed
+            ),
+            format!("light    {}", tracked.light.name()),
+        ]),
+        None => {
+            let error = snapshot
+                .lost
+                .iter()
+                .find(|(lost, _)| lost.elements.norad_id == elements.norad_id)
+                .map_or_else(String::new, |(_, error)| error.to_string());
+            lines.extend([format!("lost     {}", error), String::new(), String::new()]);
+        }
+    }
+    let age = sat
+        .element_age(snapshot.time)
+        .map(|days| format!(" {:+.2}d", days))
+        .unwrap_or_default();
+    lines.extend([
+        format!(
+            "epoch    {}{}",
+            elements.datetime.format("%Y-%m-%d %H:%M:%S"),
+            age
+        ),
+        format!("incl.    {:.4}°", elements.inclination),
+        format!("RAAN     {:.4}°", elements.right_ascension),
+        format!("ecc.     {:.7}", elements.eccentricity),
+        format!("arg. per {:.4}°", elements.argument_of_perigee),
+        format!("mean an. {:.4}°", elements.mean_anomaly),
+        format!(
+            "mean mo. {:.8}/day {:.1} min",
+            elements.mean_motion,
+            1440.0 / elements.mean_motion
+        ),
+        format!("B*       {:.4e} 1/ER", elements.drag_term),
+        format!("ndot/2   {:.4e} rev/day²", elements.mean_motion_dot),
+        format!(
+            "rev. no. {}  set {}",
+            elements.revolution_number, elements.element_set_number
+        ),
+    ]);
+    frame.render_widget(
+        Paragraph::new(lines.join("\n")).block(
+            Block::default()
+                .title(full_name(elements))
+                .borders(Borders::ALL),
+        ),
+        area,
+    );
+}
+
+/// upcoming passes over the observer, in the order chosen with `s` / `S`
+fn draw_passes(frame: &mut Frame, app: &App, snapshot: &Snapshot, area: Rect) {
+    let rows: Vec<Row> = app
+        .upcoming_passes(snapshot.time, &snapshot.shown)
+        .map(|pass| {
+            let row = Row::new(vec![
+                pass.name.clone(),
+                format_utc(pass.aos),
+                format!("{:.0}", pass.aos_azimuth),
+                format_utc_time(pass.tca),
+                format!("{:.1}", pass.max_elevation),
+                format_utc_time(pass.los),
+                format!("{:.0}", pass.los_azimuth),
+                format_span(pass.duration()),
+                pass.magnitude
+                    .map(|magnitude| format!("{:.1}", magnitude))
+                    .unwrap_or_default(),
+            ]);
+            if pass.aos <= snapshot.time {
+                row.green()
+            } else {
+                row
+            }
+        })
+        .collect();
+    let title = format!(
+        "{} above {}°{} (sorted by {}{}, s/S to change, o to filter, p to hide)",
+        if app.visible_only {
+            "Visible passes"
+        } else {
+            "Passes"
+        },
+        app.args.min_elevation,
+        if app.predicting() { ", predicting" } else { "" },
+        app.pass_sort.name(),
+        if app.pass_reverse { ", reversed" } else { "" }
+    );
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(8),
+                Constraint::Length(19),
+                Constraint::Length(4),
+                Constraint::Length(8),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Length(4),
+                Constraint::Length(6),
+                Constraint::Length(4),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "sat",
+                "AOS (UTC)",
+                "az°",
+                "TCA",
+                "el°",
+                "LOS",
+                "az°",
+                "length",
+                "mag",
+            ])
+            .bold(),
+        )
+        .block(Block::default().title(title).borders(Borders::ALL)),
+        area,
+    );
+}
+
+/// upcoming shadow entries and exits of all satellites
+fn draw_eclipses(frame: &mut Frame, app: &App, snapshot: &Snapshot, area: Rect) {
+    let items: Vec<ListItem> = app
+        .eclipses
+        .iter()
+        .filter(|event| event.time >= snapshot.time)
+        .filter(|event| snapshot.shows(event.norad_id))
+        .map(|event| {
+            let item = ListItem::new(format!(
+                "{} {} {}",
+                format_utc_time(event.time),
+                event.name,
+                event.describe()
+            ))
+            .fg(light_color(event.to));
+            if app.selected == Some(event.norad_id) {
+                item.bold()
+            } else {
+                item
+            }
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(
+            Block::default()
+                .title("Eclipses (UTC)")
+                .borders(Borders::ALL),
+        ),
+        area,
+    );
+}
+
+fn draw_lost(frame: &mut Frame, snapshot: &Snapshot, area: Rect) {
+    let items: Vec<ListItem> = snapshot
+        .lost
+        .iter()
+        .map(|(sat, error)| {
+            ListItem::new(format!("{} {}", full_name(&sat.elements), error.short()))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items)
+            .block(Block::default().title("Lost").borders(Borders::ALL))
+            .red(),
+        area,
+    );
+}
@@ -0,0 +1,143 @@
+use ratatui::{
+    layout::{Margin, Rect},
+    widgets::canvas::MapResolution,
+};
+
+/// deepest zoom, about 7 degrees of longitude across
+const MAX_ZOOM: f64 = 50.0;
+/// zoom level from which the detailed coastline is drawn
+const HIGH_RESOLUTION_ZOOM: f64 = 2.0;
+
+/// The part of the map shown on the canvas
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    /// canvas coordinates of the middle of the view
+    pub center: (f64, f64),
+    /// 1 shows the whole map, 2 half its width and height, ...
+    pub zoom: f64,
+    /// canvas x and y range of the whole map
+    extent: ([f64; 2], [f64; 2]),
+}
+
+fn mid([low, high]: [f64; 2]) -> f64 {
+    (low + high) / 2.0
+}
+
+/// `[low, high]` of `span` around `center`, slid back inside `full`
+fn window(center: f64, span: f64, [low, high]: [f64; 2]) -> [f64; 2] {
+    let start = (center - span / 2.0).clamp(low, high - span);
+    [start, start + span]
+}
+
+impl Viewport {
+    /// the whole of a map covering `extent`
+    pub fn new(extent: ([f64; 2], [f64; 2])) -> Self {
+        Viewport {
+            center: (mid(extent.0), mid(extent.1)),
+            zoom: 1.0,
+            extent,
+        }
+    }
+
+    /// changes the extent of the whole map, e.g. to keep a round map round when
+    /// the terminal is resized, keeping the view on it
+    pub fn fit(&mut self, extent: ([f64; 2], [f64; 2])) {
+        self.extent = extent;
+        self.settle();
+    }
+
+    fn span(&self) -> (f64, f64) {
+        let (x, y) = self.extent;
+        ((x[1] - x[0]) / self.zoom, (y[1] - y[0]) / self.zoom)
+    }
+
+    /// x and y bounds for the canvas
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let (width, height) = self.span();
+        (
+            window(self.center.0, width, self.extent.0),
+            window(self.center.1, height, self.extent.1),
+        )
+    }
+
+    pub fn resolution(&self) -> MapResolution {
+        if self.zoom >= HIGH_RESOLUTION_ZOOM {
+            MapResolution::High
+        } else {
+            MapResolution::Low
+        }
+    }
+
+    /// zooms by `factor` keeping the canvas point `fixed` in the same place on screen
+    pub fn zoom_at(&mut self, factor: f64, fixed: (f64, f64)) {
+        let zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        let scale = self.zoom / zoom;
+        self.center = (
+            fixed.0 + (self.center.0 - fixed.0) * scale,
+            fixed.1 + (self.center.1 - fixed.1) * scale,
+        );
+        self.zoom = zoom;
+        self.settle();
+    }
+
+    /// centers the view on a canvas point, as far as the map edges allow
+    pub fn center_on(&mut self, point: (f64, f64)) {
+        self.center = point;
+        self.settle();
+    }
+
+    /// moves the view by a fraction of its width and height
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let (width, height) = self.span();
+        self.center = (self.center.0 + dx * width, self.center.1 + dy * height);
+        self.settle();
+    }
+
+    /// pulls the center back so the view stays on the map
+    fn settle(&mut self) {
+        let (x, y) = self.bounds();
+        self.center = (mid(x), mid(y));
+    }
+
+    /// canvas coordinates under a terminal cell of `area`, the canvas including its border
+    pub fn at_cell(&self, area: Rect, column: u16, row: u16) -> Option<(f64, f64)> {
+        let inner = area.inner(&Margin::new(1, 1));
+        if column < inner.left()
+            || column >= inner.right()
+            || row < inner.top()
+            || row >= inner.bottom()
+        {
+            return None;
+        }
+        let (x, y) = self.bounds();
+        let fx = (column - inner.x) as f64 + 0.5;
+        let fy = (row - inner.y) as f64 + 0.5;
+        Some((
+            x[0] + fx / inner.width as f64 * (x[1] - x[0]),
+            y[1] - fy / inner.height as f64 * (y[1] - y[0]),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORLD: ([f64; 2], [f64; 2]) = ([-180.0, 180.0], [-90.0, 90.0]);
+
+    #[test]
+    fn zoom_keeps_fixed_point_and_stays_on_map() {
+        let mut view = Viewport::new(WORLD);
+        view.zoom_at(4.0, (90.0, 45.0));
+        let (x, y) = view.bounds();
+        // the fixed point stays a quarter of the way in from the east and north edges
+        assert_eq!(x, [22.5, 112.5]);
+        assert_eq!(y, [11.25, 56.25]);
+        view.pan(10.0, 10.0);
+        let (x, y) = view.bounds();
+        assert_eq!(x, [90.0, 180.0]);
+        assert_eq!(y, [45.0, 90.0]);
+        view.zoom_at(0.01, (0.0, 0.0));
+        assert_eq!(view.bounds(), WORLD);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    eclipse::{illumination, Illumination},
+    frames::{teme_to_ecef, teme_to_ecef_state, EarthOrientation},
+    observer::Observer,
+    propagation::Propagator,
+    sun::{subsolar_point, sun_elevation, sun_position, TWILIGHT},
+};
+use hifitime::prelude::*;
+use std::f64::consts::PI;
+
+/// Assumed standard magnitude of a Kuiper satellite: its brightness at 1000 km range and
+/// 90° phase angle. This is a placeholder, not a measured value: pass `--std-mag` with a
+/// published or observed figure for anything better than a rough guide.
+pub const KUIPER_STANDARD_MAGNITUDE: f64 = 5.0;
+/// the sun has to be lower than this at the observer (degrees), i.e. after civil twilight
+pub const DARK_SUN_ELEVATION: f64 = TWILIGHT[0];
+
+/// Standard magnitudes from `--std-mag`, by NORAD ID with a fallback for the rest. A
+/// satellite without one is still checked for visibility, but gets no magnitude.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StandardMagnitudes {
+    pub by_norad: Vec<(u64, f64)>,
+    pub fallback: Option<f64>,
+}
+
+impl StandardMagnitudes {
+    pub fn of(&self, norad_id: u64) -> Option<f64> {
+        self.by_norad
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == norad_id)
+            .map(|(_, magnitude)| *magnitude)
+            .or(self.fallback)
+    }
+}
+
+/// Visual magnitude of a diffusely reflecting sphere with `standard` magnitude at `range`
+/// km, seen at `phase` radians between the directions to the sun and the observer (0 is
+/// fully lit)
+pub fn visual_magnitude(standard: f64, range: f64, phase: f64) -> f64 {
+    let lit = ((PI - phase) * phase.cos() + phase.sin()).max(1e-9);
+    standard + 5.0 * (range / 1000.0).log10() - 2.5 * lit.log10()
+}
+
+/// How a satellite is seen when it is visible to the eye or a telescope
+#[derive(Clone, Copy, Debug)]
+pub struct Sighting {
+    /// km
+    pub range: f64,
+    /// radians, see [`visual_magnitude`]
+    pub phase: f64,
+}
+
+/// The sighting if the satellite is visible to the eye or a telescope at `time`: above the
+/// horizon and sunlit, with the observer in darkness
+pub fn sighting(
+    propagator: &Propagator,
+    observer: &Observer,
+    eop: &EarthOrientation,
+    time: Epoch,
+) -> Option<Sighting> {
+    let subsolar = subsolar_point(time, eop);
+    let observer_sun = sun_elevation(&subsolar, observer.location.lat, observer.location.lon);
+    if observer_sun > DARK_SUN_ELEVATION {
+        return None;
+    }
+    let prediction = propagator.propagate(time).ok()?;
+    let sun = sun_position(time);
+    if illumination(prediction.position, sun) != Illumination::Sunlit {
+        return None;
+    }
+    let ecef = teme_to_ecef_state(prediction.position, prediction.velocity, time, eop);
+    let look = observer.look_angles(&ecef);
+    if look.elevation < 0.0 {
+        return None;
+    }
+    let sun = teme_to_ecef(sun, time, eop);
+    let to_sun: Vec<f64> = sun.iter().zip(ecef.position).map(|(s, p)| s - p).collect();
+    let to_observer: Vec<f64> = observer
+        .ecef()
+        .iter()
+        .zip(ecef.position)
+        .map(|(o, p)| o - p)
+        .collect();
+    let norm = |v: &[f64]| v.iter().map(|c| c.powi(2)).sum::<f64>().sqrt();
+    let phase = (to_sun
+        .iter()
+        .zip(&to_observer)
+        .map(|(a, b)| a * b)
+        .sum::<f64>()
+        / (norm(&to_sun) * norm(&to_observer)))
+    .clamp(-1.0, 1.0)
+    .acos();
+    Some(Sighting {
+        range: look.range,
+        phase,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magnitude_scales_with_range_and_phase() {
+        let standard = KUIPER_STANDARD_MAGNITUDE;
+        // at the reference range and half phase the standard magnitude comes back
+        assert!((visual_magnitude(standard, 1000.0, PI / 2.0) - standard).abs() < 1e-9);
+        // ten times further is five magnitudes fainter
+        let far = visual_magnitude(standard, 10000.0, PI / 2.0);
+        assert!((far - standard - 5.0).abs() < 1e-9);
+        // fully lit is brighter than half lit, which is brighter than a thin crescent
+        assert!(visual_magnitude(standard, 1000.0, 0.0) < standard);
+        assert!(visual_magnitude(standard, 1000.0, 2.8) > standard + 2.0);
+    }
+
+    #[test]
+    fn standard_magnitude_lookup() {
+        let magnitudes = StandardMagnitudes {
+            by_norad: vec![(25544, -1.8)],
+            fallback: Some(6.0),
+        };
+        assert_eq!(magnitudes.of(25544), Some(-1.8));
+        assert_eq!(magnitudes.of(58012), Some(6.0));
+        assert_eq!(StandardMagnitudes::default().of(58012), None);
+    }
+}